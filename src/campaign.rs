@@ -0,0 +1,196 @@
+use crate::{
+    GameState, TITLE,
+    components::{GridLayer, OnGameScreen, Position},
+    events::ShowMessage,
+    highscore::{ORGANIZATION, QUALIFIER},
+    maze::{Maze, Mazes},
+    replay::Playback,
+    resources::{ArenaSize, ShownArena, TileSize},
+    simulation::GameSim,
+};
+use anyhow::{Result, anyhow};
+use bevy::prelude::*;
+use directories::ProjectDirs;
+use std::{fs::File, io::Write};
+
+/// Number of food that has to be eaten on a level of the campaign to open its exit.
+pub const EXIT_FOOD: u32 = 10;
+pub const EXIT_COLOR: Color = Color::srgb(1.0, 1.0, 1.0);
+
+const CAMPAIGN: &str = "campaign";
+
+/// Plays the mazes one after another and remembers the highest level that was reached.
+pub struct CampaignPlugin;
+
+impl Plugin for CampaignPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (
+                    spawn_exit.run_if(on_event::<ExitOpened>),
+                    next_level.run_if(on_event::<LevelCompleted>),
+                )
+                    .run_if(in_state(GameState::Game)),
+                save_campaign_progress.run_if(
+                    resource_changed::<CampaignProgress>
+                        .and(not(resource_added::<CampaignProgress>)),
+                ),
+                resize.run_if(resource_changed::<TileSize>),
+            ),
+        )
+        .add_event::<ExitOpened>()
+        .add_event::<LevelCompleted>()
+        .insert_resource(load_campaign_progress());
+    }
+}
+
+/// The exit to the next level was opened at the position.
+#[derive(Event)]
+pub struct ExitOpened(pub Position);
+
+/// The level before the given one was completed.
+#[derive(Event)]
+pub struct LevelCompleted(pub usize);
+
+#[derive(Component)]
+struct Exit;
+
+/// Index of the highest level of the campaign that was reached. New campaigns start there.
+#[derive(Default, Resource)]
+pub struct CampaignProgress {
+    pub unlocked: usize,
+}
+
+/// Returns the mazes of the campaign in the order they are played, if all of them are loaded.
+pub fn campaign_mazes(mazes: &Mazes, assets: &Assets<Maze>) -> Option<Vec<Maze>> {
+    mazes
+        .built_in()
+        .iter()
+        .map(|handle| assets.get(handle).cloned())
+        .collect()
+}
+
+fn spawn_exit(
+    mut commands: Commands,
+    mut reader: EventReader<ExitOpened>,
+    tile_size: Res<TileSize>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for ExitOpened(pos) in reader.read() {
+        commands.spawn((
+            Exit,
+            *pos,
+            GridLayer::Food,
+            Mesh2d(meshes.add(Rectangle::new(tile_size.0 as f32, tile_size.0 as f32))),
+            MeshMaterial2d(materials.add(EXIT_COLOR)),
+            OnGameScreen,
+        ));
+    }
+}
+
+fn resize(
+    tile_size: Res<TileSize>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut q: Query<&mut Mesh2d, With<Exit>>,
+) {
+    let mesh = meshes.add(Rectangle::new(tile_size.0 as f32, tile_size.0 as f32));
+
+    for mut mesh2d in q.iter_mut() {
+        mesh2d.0 = mesh.clone();
+    }
+}
+
+/// Removes the exit, unlocks the next level and announces it. Replays that are played back
+/// don't unlock levels.
+fn next_level(
+    mut commands: Commands,
+    mut reader: EventReader<LevelCompleted>,
+    sim: Res<GameSim>,
+    playback: Option<Res<Playback>>,
+    exits: Query<Entity, With<Exit>>,
+    mut progress: ResMut<CampaignProgress>,
+    mut arena: ResMut<ShownArena>,
+    mut show_message_writer: EventWriter<ShowMessage>,
+) {
+    let Some(LevelCompleted(level)) = reader.read().last() else {
+        return;
+    };
+
+    for exit in exits.iter() {
+        commands.entity(exit).despawn();
+    }
+
+    if sim.is_game_over() {
+        return;
+    }
+
+    if playback.is_none() && *level > progress.unlocked {
+        progress.unlocked = *level;
+    }
+
+    arena.set_if_neq(ShownArena(ArenaSize::new(sim.width(), sim.height())));
+
+    show_message_writer.write(ShowMessage {
+        text: format!("Maze {}", level + 1),
+        position: sim.head(),
+    });
+}
+
+/// Load the campaign progress from platform specific data directory (e.g.
+/// `$HOME/.local/share/diplopod/campaign`). If the file is invalid or inaccessible the campaign
+/// starts at the first level.
+fn load_campaign_progress() -> CampaignProgress {
+    match read_campaign_progress_from_file() {
+        Ok(progress) => progress,
+        Err(e) => {
+            warn!("{}", e);
+            CampaignProgress::default()
+        }
+    }
+}
+
+/// Reads the campaign progress from the platform specific data directory. The file contains the
+/// number of the highest level that was reached, starting at one.
+fn read_campaign_progress_from_file() -> Result<CampaignProgress> {
+    if let Some(projects_dirs) = ProjectDirs::from(QUALIFIER, ORGANIZATION, TITLE) {
+        let level: usize = std::fs::read_to_string(projects_dirs.data_dir().join(CAMPAIGN))?
+            .trim()
+            .parse()?;
+
+        Ok(CampaignProgress {
+            unlocked: level.saturating_sub(1),
+        })
+    } else {
+        Err(anyhow!(
+            "Unable to retrieve path to data directory. Campaign progress can't be read."
+        ))
+    }
+}
+
+/// Save the campaign progress to platform specific data directory (e.g.
+/// `$HOME/.local/share/diplopod/campaign`). Errors will be logged but otherwise ignored.
+fn save_campaign_progress(progress: Res<CampaignProgress>) {
+    if let Err(e) = write_campaign_progress_to_file(&progress) {
+        warn!("{}", e)
+    }
+}
+
+/// Writes the campaign progress to the platform specific data directory. The file and all
+/// necessary directories are created by the function.
+fn write_campaign_progress_to_file(progress: &CampaignProgress) -> Result<()> {
+    if let Some(projects_dirs) = ProjectDirs::from(QUALIFIER, ORGANIZATION, TITLE) {
+        let data_dir = projects_dirs.data_dir();
+        std::fs::create_dir_all(data_dir)?;
+
+        let mut file = File::create(data_dir.join(CAMPAIGN))?;
+        write!(file, "{}", progress.unlocked + 1)?;
+
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Unable to retrieve path to data directory. Campaign progress can't be written."
+        ))
+    }
+}
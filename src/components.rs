@@ -1,20 +1,52 @@
 use bevy::prelude::*;
 
-#[derive(Component, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Component, Default, Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
 }
 
+/// Position of a moving entity before the last tick.
+#[derive(Component, Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PreviousPosition(pub Position);
+
 #[derive(Component)]
 pub struct FadingText(pub f32);
 
+/// Layer of an entity that is placed on the grid of the arena by its `Position`. The layer
+/// decides which entities are drawn above others.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridLayer {
+    Food,
+    Diplopod,
+    Wall,
+    /// Messages are drawn above everything else.
+    Message,
+}
+
+impl GridLayer {
+    pub fn z(&self) -> f32 {
+        match self {
+            GridLayer::Food => 1.0,
+            GridLayer::Diplopod => 1.5,
+            GridLayer::Wall => 2.0,
+            GridLayer::Message => 3.0,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Default, Eq, PartialEq, Debug, Hash, States)]
 pub enum GameState {
     #[default]
     Menu,
     Game,
+    NameEntry,
     Highscore,
+    Replays,
+    Editor,
+    Winner,
+    Settings,
+    Controls,
 }
 
 #[derive(Component)]
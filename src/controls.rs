@@ -0,0 +1,779 @@
+use crate::{
+    GameState, TITLE,
+    highscore::{ORGANIZATION, QUALIFIER},
+};
+use anyhow::{Result, anyhow};
+use bevy::{color::palettes::css::ANTIQUE_WHITE, platform::collections::HashMap, prelude::*};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, fs::File, io::Write};
+
+/// Maps the actions of the game to keys and gamepad buttons and adds a screen to rebind them.
+pub struct ControlsPlugin;
+
+const TITLE_COLOR: Color = Color::Srgba(ANTIQUE_WHITE);
+const BUTTON_TEXT_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const BUTTON_BACKGROUND_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+const BUTTON_SELECTED_BACKGROUND_COLOR: Color = Color::srgb(0.25, 0.25, 0.25);
+
+const CONTROLS: &str = "controls.ron";
+const DEADZONE_STEP: f32 = 0.1;
+const MIN_DEADZONE: f32 = 0.1;
+const MAX_DEADZONE: f32 = 0.9;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Controls), setup_controls)
+            .add_systems(
+                Update,
+                (
+                    (keyboard, gamepad, pointer).run_if(in_state(GameState::Controls)),
+                    assign_gamepads,
+                    update_rows.run_if(
+                        resource_changed::<Controls>
+                            .or(resource_exists_and_changed::<ControlsScreen>),
+                    ),
+                    save_controls
+                        .run_if(resource_changed::<Controls>.and(not(resource_added::<Controls>))),
+                ),
+            )
+            .add_systems(
+                OnExit(GameState::Controls),
+                (
+                    crate::despawn_screen::<OnControlsScreen>,
+                    remove_controls_screen,
+                ),
+            )
+            .init_resource::<GamepadPlayers>()
+            .insert_resource(load_controls());
+    }
+}
+
+/// An action of the game that can be bound to keys and gamepad buttons. The directions steer the
+/// diplopod of the first player, the second directions the one of the second player in the
+/// versus mode. Both steer the diplopod in the other modes and navigate the screens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    SecondUp,
+    SecondDown,
+    SecondLeft,
+    SecondRight,
+    Pause,
+    Confirm,
+    Back,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::SecondUp,
+        Action::SecondDown,
+        Action::SecondLeft,
+        Action::SecondRight,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+    ];
+
+    /// The actions that steer the diplopod of the first player.
+    pub const DIRECTIONS: [Action; 4] = [Action::Up, Action::Down, Action::Left, Action::Right];
+
+    /// The actions that steer the diplopod of the second player.
+    pub const SECOND_DIRECTIONS: [Action; 4] = [
+        Action::SecondUp,
+        Action::SecondDown,
+        Action::SecondLeft,
+        Action::SecondRight,
+    ];
+
+    /// Direction in the arena of the actions that steer a diplopod, zero for the others.
+    pub fn direction(&self) -> IVec2 {
+        match self {
+            Action::Up | Action::SecondUp => IVec2::Y,
+            Action::Down | Action::SecondDown => IVec2::NEG_Y,
+            Action::Left | Action::SecondLeft => IVec2::NEG_X,
+            Action::Right | Action::SecondRight => IVec2::X,
+            _ => IVec2::ZERO,
+        }
+    }
+
+    /// The direction of the second player that navigates the screens like the direction.
+    fn second(&self) -> Option<Action> {
+        match self {
+            Action::Up => Some(Action::SecondUp),
+            Action::Down => Some(Action::SecondDown),
+            Action::Left => Some(Action::SecondLeft),
+            Action::Right => Some(Action::SecondRight),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Action::SecondUp => write!(f, "Player 2 Up"),
+            Action::SecondDown => write!(f, "Player 2 Down"),
+            Action::SecondLeft => write!(f, "Player 2 Left"),
+            Action::SecondRight => write!(f, "Player 2 Right"),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// The keys and gamepad buttons bound to an action.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Binding {
+    pub keys: Vec<KeyCode>,
+    pub buttons: Vec<GamepadButton>,
+}
+
+/// The bindings of all actions and how far the left stick has to be tilted to steer the diplopod.
+/// A key or button is bound to a single action at most.
+#[derive(Clone, Debug, PartialEq, Resource, Serialize, Deserialize)]
+pub struct Controls {
+    bindings: BTreeMap<Action, Binding>,
+    pub deadzone: f32,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        let binding = |keys: &[KeyCode], buttons: &[GamepadButton]| Binding {
+            keys: keys.to_vec(),
+            buttons: buttons.to_vec(),
+        };
+
+        Self {
+            bindings: BTreeMap::from([
+                (
+                    Action::Up,
+                    binding(&[KeyCode::KeyW, KeyCode::KeyK], &[GamepadButton::DPadUp]),
+                ),
+                (
+                    Action::Down,
+                    binding(&[KeyCode::KeyS, KeyCode::KeyJ], &[GamepadButton::DPadDown]),
+                ),
+                (
+                    Action::Left,
+                    binding(&[KeyCode::KeyA, KeyCode::KeyH], &[GamepadButton::DPadLeft]),
+                ),
+                (
+                    Action::Right,
+                    binding(&[KeyCode::KeyD, KeyCode::KeyL], &[GamepadButton::DPadRight]),
+                ),
+                (Action::SecondUp, binding(&[KeyCode::ArrowUp], &[])),
+                (Action::SecondDown, binding(&[KeyCode::ArrowDown], &[])),
+                (Action::SecondLeft, binding(&[KeyCode::ArrowLeft], &[])),
+                (Action::SecondRight, binding(&[KeyCode::ArrowRight], &[])),
+                (
+                    Action::Pause,
+                    binding(&[KeyCode::Space, KeyCode::KeyP], &[GamepadButton::Start]),
+                ),
+                (
+                    Action::Confirm,
+                    binding(&[KeyCode::Enter], &[GamepadButton::South]),
+                ),
+                (
+                    Action::Back,
+                    binding(&[KeyCode::Escape], &[GamepadButton::East]),
+                ),
+            ]),
+            deadzone: MAX_DEADZONE,
+        }
+    }
+}
+
+impl Controls {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.bindings
+            .get(&action)
+            .map_or(&[], |binding| &binding.keys)
+    }
+
+    pub fn buttons(&self, action: Action) -> &[GamepadButton] {
+        self.bindings
+            .get(&action)
+            .map_or(&[], |binding| &binding.buttons)
+    }
+
+    /// The keys of the action and, for a direction, the keys of the same direction of the second
+    /// player, which navigate the screens as well.
+    fn navigation_keys(&self, action: Action) -> impl Iterator<Item = KeyCode> {
+        let second = action.second().map_or(&[][..], |second| self.keys(second));
+        self.keys(action).iter().chain(second).copied()
+    }
+
+    fn navigation_buttons(&self, action: Action) -> impl Iterator<Item = GamepadButton> {
+        let second = action
+            .second()
+            .map_or(&[][..], |second| self.buttons(second));
+        self.buttons(action).iter().chain(second).copied()
+    }
+
+    pub fn just_pressed(&self, action: Action, keyboard_input: &ButtonInput<KeyCode>) -> bool {
+        keyboard_input.any_just_pressed(self.navigation_keys(action))
+    }
+
+    pub fn just_released(&self, action: Action, keyboard_input: &ButtonInput<KeyCode>) -> bool {
+        keyboard_input.any_just_released(self.navigation_keys(action))
+    }
+
+    pub fn gamepad_just_pressed(&self, action: Action, gamepad: &Gamepad) -> bool {
+        gamepad.any_just_pressed(self.navigation_buttons(action))
+    }
+
+    pub fn gamepad_just_released(&self, action: Action, gamepad: &Gamepad) -> bool {
+        gamepad.any_just_released(self.navigation_buttons(action))
+    }
+
+    /// Whether the key is bound to any action.
+    pub fn is_key_bound(&self, key: KeyCode) -> bool {
+        self.bindings
+            .values()
+            .any(|binding| binding.keys.contains(&key))
+    }
+
+    /// Whether the button is bound to any action.
+    pub fn is_button_bound(&self, button: GamepadButton) -> bool {
+        self.bindings
+            .values()
+            .any(|binding| binding.buttons.contains(&button))
+    }
+
+    /// The other action the key is bound to, if any.
+    fn key_owner(&self, action: Action, key: KeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(other, binding)| **other != action && binding.keys.contains(&key))
+            .map(|(other, _)| *other)
+    }
+
+    fn button_owner(&self, action: Action, button: GamepadButton) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(other, binding)| **other != action && binding.buttons.contains(&button))
+            .map(|(other, _)| *other)
+    }
+
+    /// Direction the left stick of the gamepad is tilted in along its dominant axis, or zero if
+    /// it is tilted less than the deadzone.
+    pub fn stick_direction(&self, gamepad: &Gamepad) -> IVec2 {
+        let x = gamepad.get(GamepadAxis::LeftStickX).unwrap_or(0.0);
+        let y = gamepad.get(GamepadAxis::LeftStickY).unwrap_or(0.0);
+
+        if x.abs() >= self.deadzone && x.abs() >= y.abs() {
+            IVec2::new(x.signum() as i32, 0)
+        } else if y.abs() >= self.deadzone {
+            IVec2::new(0, y.signum() as i32)
+        } else {
+            IVec2::ZERO
+        }
+    }
+
+    /// Binds the key to the action or unbinds it if it already is. The last key of an action
+    /// that is needed to navigate the screens is kept. A key of another action isn't bound, the
+    /// other action is returned instead.
+    fn toggle_key(&mut self, action: Action, key: KeyCode) -> Result<(), Action> {
+        if let Some(other) = self.key_owner(action, key) {
+            return Err(other);
+        }
+
+        let keys = &mut self.bindings.entry(action).or_default().keys;
+        match keys.iter().position(|bound| *bound == key) {
+            Some(_) if keys.len() == 1 && REQUIRED.contains(&action) => (),
+            Some(index) => {
+                keys.remove(index);
+            }
+            None => keys.push(key),
+        }
+
+        Ok(())
+    }
+
+    /// Binds the gamepad button to the action or unbinds it if it already is, see
+    /// [`Controls::toggle_key`].
+    fn toggle_button(&mut self, action: Action, button: GamepadButton) -> Result<(), Action> {
+        if let Some(other) = self.button_owner(action, button) {
+            return Err(other);
+        }
+
+        let buttons = &mut self.bindings.entry(action).or_default().buttons;
+        match buttons.iter().position(|bound| *bound == button) {
+            Some(_) if buttons.len() == 1 && REQUIRED.contains(&action) => (),
+            Some(index) => {
+                buttons.remove(index);
+            }
+            None => buttons.push(button),
+        }
+
+        Ok(())
+    }
+}
+
+/// The player each connected gamepad steers in the versus mode. A gamepad gets the lowest
+/// player that is free when it is connected and keeps it until it is disconnected.
+#[derive(Default, Resource)]
+pub struct GamepadPlayers(HashMap<Entity, usize>);
+
+impl GamepadPlayers {
+    pub fn player(&self, gamepad: Entity) -> Option<usize> {
+        self.0.get(&gamepad).copied()
+    }
+}
+
+fn assign_gamepads(
+    connected: Query<Entity, Added<Gamepad>>,
+    mut disconnected: RemovedComponents<Gamepad>,
+    mut players: ResMut<GamepadPlayers>,
+) {
+    for gamepad in disconnected.read() {
+        players.0.remove(&gamepad);
+    }
+
+    for gamepad in connected.iter() {
+        let player = (0..)
+            .find(|player| !players.0.values().any(|other| other == player))
+            .unwrap_or_default();
+        players.0.insert(gamepad, player);
+    }
+}
+
+#[derive(Component)]
+struct OnControlsScreen;
+
+/// The actions that are used on the controls screen.
+const NAVIGATION: [Action; 6] = [
+    Action::Up,
+    Action::Down,
+    Action::Left,
+    Action::Right,
+    Action::Confirm,
+    Action::Back,
+];
+
+/// The actions that always keep at least one key and one gamepad button, so that the screens can
+/// still be navigated.
+const REQUIRED: [Action; 4] = [Action::Up, Action::Down, Action::Confirm, Action::Back];
+
+/// A row of the controls screen.
+#[derive(Clone, Copy, Component, PartialEq)]
+enum Row {
+    Binding(Action),
+    Deadzone,
+    Reset,
+    Back,
+}
+
+impl Row {
+    const COUNT: usize = Action::ALL.len() + 3;
+
+    fn at(index: usize) -> Self {
+        match index.checked_sub(Action::ALL.len()) {
+            None => Row::Binding(Action::ALL[index]),
+            Some(0) => Row::Deadzone,
+            Some(1) => Row::Reset,
+            _ => Row::Back,
+        }
+    }
+}
+
+/// Text of a row of the controls screen.
+#[derive(Component)]
+struct RowText(Row);
+
+/// The selected row, whether it waits for a key or gamepad button to bind to its action and the
+/// last rejected binding: the action and the other action that owns the key or button.
+#[derive(Default, Resource)]
+struct ControlsScreen {
+    selected: usize,
+    waiting: bool,
+    conflict: Option<(Action, Action)>,
+}
+
+impl ControlsScreen {
+    fn row(&self) -> Row {
+        Row::at(self.selected)
+    }
+}
+
+/// Moves between the rows with up and down, changes the deadzone with left and right and
+/// activates the selected row with confirm. While a row waits for a binding, the next key that
+/// is released is bound to its action, or unbound if it already was. Taking the key on its
+/// release keeps it from also navigating the screen. Back cancels the binding.
+fn keyboard(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut controls: ResMut<Controls>,
+    mut screen: ResMut<ControlsScreen>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if screen.waiting {
+        if let (Row::Binding(action), Some(key)) =
+            (screen.row(), keyboard_input.get_just_released().next())
+        {
+            if !controls.keys(Action::Back).contains(key) {
+                screen.conflict = controls
+                    .toggle_key(action, *key)
+                    .err()
+                    .map(|other| (action, other));
+            }
+            screen.waiting = false;
+        }
+        return;
+    }
+
+    for action in NAVIGATION {
+        if controls.just_released(action, &keyboard_input) {
+            navigate(action, &mut controls, &mut screen, &mut game_state);
+            return;
+        }
+    }
+}
+
+/// Works like the keyboard with the buttons of the gamepads.
+fn gamepad(
+    gamepads: Query<&Gamepad>,
+    mut controls: ResMut<Controls>,
+    mut screen: ResMut<ControlsScreen>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for gamepad in gamepads.iter() {
+        if screen.waiting {
+            if let (Row::Binding(action), Some(button)) =
+                (screen.row(), gamepad.get_just_released().next())
+            {
+                if !controls.buttons(Action::Back).contains(button) {
+                    screen.conflict = controls
+                        .toggle_button(action, *button)
+                        .err()
+                        .map(|other| (action, other));
+                }
+                screen.waiting = false;
+            }
+            continue;
+        }
+
+        for action in NAVIGATION {
+            if controls.gamepad_just_released(action, gamepad) {
+                navigate(action, &mut controls, &mut screen, &mut game_state);
+                return;
+            }
+        }
+    }
+}
+
+/// Selects the row the mouse pointer hovers over and activates the row that was clicked or
+/// touched.
+fn pointer(
+    interactions: Query<(&Interaction, &Row), Changed<Interaction>>,
+    mut controls: ResMut<Controls>,
+    mut screen: ResMut<ControlsScreen>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, row) in interactions.iter() {
+        let Some(index) = (0..Row::COUNT).find(|index| Row::at(*index) == *row) else {
+            continue;
+        };
+
+        match interaction {
+            Interaction::Pressed => {
+                screen.selected = index;
+                screen.waiting = false;
+                screen.conflict = None;
+                activate(&mut controls, &mut screen, &mut game_state);
+            }
+            Interaction::Hovered if !screen.waiting && screen.selected != index => {
+                screen.selected = index;
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Changes the controls screen according to the action. The resources are only changed, and the
+/// controls only saved, if the action does something.
+fn navigate(
+    action: Action,
+    controls: &mut ResMut<Controls>,
+    screen: &mut ResMut<ControlsScreen>,
+    game_state: &mut NextState<GameState>,
+) {
+    screen.conflict = None;
+
+    match action {
+        Action::Up => screen.selected = (screen.selected + Row::COUNT - 1) % Row::COUNT,
+        Action::Down => screen.selected = (screen.selected + 1) % Row::COUNT,
+        Action::Left if screen.row() == Row::Deadzone => change_deadzone(controls, -DEADZONE_STEP),
+        Action::Right if screen.row() == Row::Deadzone => change_deadzone(controls, DEADZONE_STEP),
+        Action::Confirm => activate(controls, screen, game_state),
+        Action::Back => game_state.set(GameState::Settings),
+        _ => (),
+    }
+}
+
+/// Waits for a binding, increases the deadzone, resets the controls or returns to the settings.
+fn activate(
+    controls: &mut ResMut<Controls>,
+    screen: &mut ResMut<ControlsScreen>,
+    game_state: &mut NextState<GameState>,
+) {
+    match screen.row() {
+        Row::Binding(_) => screen.waiting = true,
+        Row::Deadzone if controls.deadzone >= MAX_DEADZONE => controls.deadzone = MIN_DEADZONE,
+        Row::Deadzone => change_deadzone(controls, DEADZONE_STEP),
+        Row::Reset => **controls = Controls::default(),
+        Row::Back => game_state.set(GameState::Settings),
+    }
+}
+
+fn change_deadzone(controls: &mut Controls, step: f32) {
+    // round to avoid that the steps accumulate floating point errors
+    controls.deadzone = ((controls.deadzone + step) * 10.0).round() / 10.0;
+    controls.deadzone = controls.deadzone.clamp(MIN_DEADZONE, MAX_DEADZONE);
+}
+
+fn row_text(row: Row, controls: &Controls, screen: &ControlsScreen) -> String {
+    if let Some((action, other)) = screen.conflict
+        && row == Row::Binding(action)
+    {
+        return format!("{}: already bound to {}", action, other);
+    }
+
+    match row {
+        Row::Binding(action) if screen.waiting && screen.row() == row => {
+            format!("{}: press a key or button, back cancels", action)
+        }
+        Row::Binding(action) => {
+            let names: Vec<String> = controls
+                .keys(action)
+                .iter()
+                .map(|key| format!("{:?}", key).trim_start_matches("Key").to_string())
+                .chain(
+                    controls
+                        .buttons(action)
+                        .iter()
+                        .map(|button| format!("{:?}", button)),
+                )
+                .collect();
+            format!("{}: {}", action, names.join(", "))
+        }
+        Row::Deadzone => format!("< Deadzone {:.1} >", controls.deadzone),
+        Row::Reset => "Reset".to_string(),
+        Row::Back => "Back".to_string(),
+    }
+}
+
+fn update_rows(
+    controls: Res<Controls>,
+    screen: Option<Res<ControlsScreen>>,
+    mut texts: Query<(&mut Text, &RowText)>,
+    mut backgrounds: Query<(&mut BackgroundColor, &Row)>,
+) {
+    let Some(screen) = screen else {
+        return;
+    };
+
+    for (mut text, row_text_of) in texts.iter_mut() {
+        text.0 = row_text(row_text_of.0, &controls, &screen);
+    }
+
+    for (mut background_color, row) in backgrounds.iter_mut() {
+        background_color.0 = if *row == screen.row() {
+            BUTTON_SELECTED_BACKGROUND_COLOR
+        } else {
+            BUTTON_BACKGROUND_COLOR
+        };
+    }
+}
+
+fn remove_controls_screen(mut commands: Commands) {
+    commands.remove_resource::<ControlsScreen>();
+}
+
+/// Creates the UI of the controls screen with a row per action followed by the deadzone of the
+/// left stick.
+fn setup_controls(mut commands: Commands, controls: Res<Controls>) {
+    let screen = ControlsScreen::default();
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            OnControlsScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Controls"),
+                        TextFont {
+                            font_size: 96.0,
+                            ..default()
+                        },
+                        TextColor(TITLE_COLOR),
+                        Node {
+                            margin: UiRect::all(Val::Px(20.0)),
+                            ..default()
+                        },
+                    ));
+
+                    for index in 0..Row::COUNT {
+                        let row = Row::at(index);
+
+                        parent
+                            .spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(640.0),
+                                    height: Val::Px(32.0),
+                                    margin: UiRect::all(Val::Px(3.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                BackgroundColor(if index == screen.selected {
+                                    BUTTON_SELECTED_BACKGROUND_COLOR
+                                } else {
+                                    BUTTON_BACKGROUND_COLOR
+                                }),
+                                row,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Text::new(row_text(row, &controls, &screen)),
+                                    TextFont {
+                                        font_size: 24.0,
+                                        ..default()
+                                    },
+                                    TextColor(BUTTON_TEXT_COLOR),
+                                    RowText(row),
+                                ));
+                            });
+                    }
+                });
+        });
+
+    commands.insert_resource(screen);
+}
+
+/// Load the controls from platform specific data directory (e.g.
+/// `$HOME/.local/share/diplopod/controls.ron`). Actions that are missing in the file, and
+/// navigation actions without any key or button, keep their default bindings. If the file is
+/// invalid or inaccessible the default controls are used.
+fn load_controls() -> Controls {
+    match read_controls_from_file() {
+        Ok(controls) => controls,
+        Err(e) => {
+            warn!("{}", e);
+            Controls::default()
+        }
+    }
+}
+
+/// Reads the controls from the platform specific data directory.
+fn read_controls_from_file() -> Result<Controls> {
+    if let Some(projects_dirs) = ProjectDirs::from(QUALIFIER, ORGANIZATION, TITLE) {
+        let content = std::fs::read_to_string(projects_dirs.data_dir().join(CONTROLS))?;
+        let mut controls: Controls = ron::from_str(&content)?;
+
+        for (action, default) in Controls::default().bindings {
+            let binding = controls.bindings.entry(action).or_insert(default.clone());
+            if REQUIRED.contains(&action) && binding.keys.is_empty() {
+                binding.keys = default.keys;
+            }
+            if REQUIRED.contains(&action) && binding.buttons.is_empty() {
+                binding.buttons = default.buttons;
+            }
+        }
+        controls.deadzone = controls.deadzone.clamp(MIN_DEADZONE, MAX_DEADZONE);
+
+        Ok(controls)
+    } else {
+        Err(anyhow!(
+            "Unable to retrieve path to data directory. Controls can't be read."
+        ))
+    }
+}
+
+/// Save the controls to platform specific data directory (e.g.
+/// `$HOME/.local/share/diplopod/controls.ron`). Errors will be logged but otherwise ignored.
+fn save_controls(controls: Res<Controls>) {
+    if let Err(e) = write_controls_to_file(&controls) {
+        warn!("{}", e)
+    }
+}
+
+/// Writes the controls to the platform specific data directory. The file and all necessary
+/// directories are created by the function.
+fn write_controls_to_file(controls: &Controls) -> Result<()> {
+    if let Some(projects_dirs) = ProjectDirs::from(QUALIFIER, ORGANIZATION, TITLE) {
+        let data_dir = projects_dirs.data_dir();
+        std::fs::create_dir_all(data_dir)?;
+
+        let mut file = File::create(data_dir.join(CONTROLS))?;
+        write!(
+            file,
+            "{}",
+            ron::ser::to_string_pretty(controls, ron::ser::PrettyConfig::default())?
+        )?;
+
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Unable to retrieve path to data directory. Controls can't be written."
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_bindings_dont_overlap() {
+        let controls = Controls::default();
+
+        for action in Action::ALL {
+            for key in controls.keys(action) {
+                assert_eq!(controls.key_owner(action, *key), None, "{:?}", key);
+            }
+            for button in controls.buttons(action) {
+                assert_eq!(controls.button_owner(action, *button), None, "{:?}", button);
+            }
+        }
+    }
+
+    #[test]
+    fn a_binding_of_another_action_is_rejected() {
+        let mut controls = Controls::default();
+
+        assert_eq!(
+            controls.toggle_key(Action::Confirm, KeyCode::Space),
+            Err(Action::Pause)
+        );
+        assert_eq!(
+            controls.toggle_button(Action::Back, GamepadButton::South),
+            Err(Action::Confirm)
+        );
+        assert_eq!(controls, Controls::default());
+
+        assert_eq!(controls.toggle_key(Action::Confirm, KeyCode::KeyE), Ok(()));
+        assert!(controls.keys(Action::Confirm).contains(&KeyCode::KeyE));
+    }
+}
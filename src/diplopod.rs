@@ -1,26 +1,34 @@
 mod setup;
 
 use crate::{
-    ARENA_HEIGHT, ARENA_WIDTH, GameState, Phase,
-    components::{OnGameScreen, Position},
-    food::{Food, SpawnFood},
-    gameover::GameOver,
-    resources::{FreePositions, LastTailPosition, TileSize},
-    wall::Wall,
+    GameState, Phase,
+    campaign::LevelCompleted,
+    components::{GridLayer, OnGameScreen, Position, PreviousPosition},
+    food::Food,
+    resources::TileSize,
+    simulation::GameSim,
 };
-use bevy::{color::palettes::css::ORANGE, prelude::*, window::PrimaryWindow};
+use bevy::{color::palettes::css::ORANGE, prelude::*};
 
 pub const DIPLOPOD_COLOR: Color = Color::Srgba(ORANGE);
+/// Color of the diplopod of the second player in the versus mode.
+pub const RIVAL_COLOR: Color = Color::srgb(1.0, 0.3, 0.6);
+pub const PLAYER_NAMES: [&str; 2] = ["Orange", "Pink"];
 
 #[derive(Event)]
 pub struct Growth(pub u8);
 
-#[derive(Default, Resource)]
+#[derive(Event)]
+pub struct Shrink(pub u8);
+
+/// The segments of a diplopod from head to tail, stored on its head.
+#[derive(Component, Default)]
 pub struct DiplopodSegments(pub Vec<Entity>);
 
 #[derive(Component)]
 pub struct DiplopodHead {
-    pub direction: Vec2,
+    /// Index of the player that controls the diplopod.
+    pub player: usize,
 }
 
 #[derive(Component)]
@@ -28,141 +36,133 @@ pub struct DiplopodSegment;
 
 pub struct DiplopodPlugin;
 
-impl Plugin for DiplopodPlugin {
-    fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Game), setup::init)
-            .add_systems(
-                Update,
-                (position_translation,)
-                    .after(Phase::Movement)
-                    .run_if(in_state(GameState::Game)),
-            );
+/// Returns the color the diplopod of the player is rendered with.
+pub fn diplopod_color(player: usize) -> Color {
+    if player == 0 {
+        DIPLOPOD_COLOR
+    } else {
+        RIVAL_COLOR
     }
 }
 
-fn position_translation(
-    windows: Query<&Window, With<PrimaryWindow>>,
-    mut q: Query<(&Position, &mut Transform), With<DiplopodSegment>>,
-    tile_size: Res<TileSize>,
-) {
-    fn convert(pos: f32, bound_window: f32, bound_game: f32, tile_size: f32) -> f32 {
-        pos / bound_game * bound_window - (bound_window / 2.) + (tile_size / 2.)
-    }
-
-    let Ok(window) = windows.single() else {
-        return;
-    };
-
-    for (pos, mut transform) in q.iter_mut() {
-        transform.translation = Vec3::new(
-            convert(
-                pos.x as f32,
-                window.width(),
-                ARENA_WIDTH as f32,
-                tile_size.0 as f32,
-            ),
-            convert(
-                pos.y as f32,
-                window.height(),
-                ARENA_HEIGHT as f32,
-                tile_size.0 as f32,
-            ),
-            1.0,
+impl Plugin for DiplopodPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Game),
+            setup::init.after(crate::simulation::setup::init),
+        )
+        .add_systems(
+            Update,
+            (
+                (crate::despawn_screen::<DiplopodSegment>, setup::init)
+                    .chain()
+                    .run_if(in_state(GameState::Game))
+                    .run_if(on_event::<LevelCompleted>),
+                resize.run_if(resource_changed::<TileSize>),
+            )
+                .chain()
+                .after(Phase::Movement),
         );
     }
 }
 
-pub fn eat(
-    mut commands: Commands,
-    mut growth_writer: EventWriter<Growth>,
-    mut spawn_food_writer: EventWriter<SpawnFood>,
-    mut game_over_writer: EventWriter<GameOver>,
-    food_positions: Query<(Entity, &Position), With<Food>>,
-    head_positions: Query<&Position, With<DiplopodHead>>,
-    wall_positions: Query<(Entity, &Position), With<Wall>>,
-    mut free_positions: ResMut<FreePositions>,
-) {
-    for head_pos in head_positions.iter() {
-        for (ent, food_pos) in food_positions.iter() {
-            if food_pos == head_pos {
-                commands.entity(ent).despawn();
-                free_positions.positions.push(*food_pos);
-                free_positions.shuffle();
-
-                growth_writer.write(Growth(1));
-                spawn_food_writer.write(SpawnFood);
-            }
-        }
-
-        for (_ent, wall_pos) in wall_positions.iter() {
-            if wall_pos == head_pos {
-                game_over_writer.write(GameOver);
-            }
+/// Despawns the food that is no longer part of the simulation because it has been eaten.
+pub fn eat(mut commands: Commands, sim: Res<GameSim>, food: Query<(Entity, &Position, &Food)>) {
+    for (ent, food_pos, food) in food.iter() {
+        if !sim.food().contains(&(*food_pos, food.0)) {
+            commands.entity(ent).despawn();
         }
     }
 }
 
+/// Spawns the segments the simulation added to the tails of the diplopods.
 pub fn growth(
     mut commands: Commands,
-    last_tail_position: Res<LastTailPosition>,
-    mut segments: ResMut<DiplopodSegments>,
+    sim: Res<GameSim>,
+    mut heads: Query<(&DiplopodHead, &mut DiplopodSegments)>,
     mut growth_reader: EventReader<Growth>,
     tile_size: Res<TileSize>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    let Some(growth) = growth_reader.read().next() else {
+    if growth_reader.read().next().is_none() {
         return;
-    };
+    }
 
     let size = (tile_size.0 - 4) as f32;
-    for _ in 0..growth.0 {
-        segments.0.push(
-            commands
-                .spawn((
-                    Mesh2d(meshes.add(Rectangle::new(size, size))),
-                    MeshMaterial2d(materials.add(DIPLOPOD_COLOR)),
-                ))
-                .insert(DiplopodSegment)
-                .insert(last_tail_position.0.unwrap())
-                .insert(OnGameScreen)
-                .id(),
-        );
+    for (head, mut segments) in heads.iter_mut() {
+        for pos in sim
+            .player_segments(head.player)
+            .iter()
+            .skip(segments.0.len())
+        {
+            segments.0.push(
+                commands
+                    .spawn((
+                        Mesh2d(meshes.add(Rectangle::new(size, size))),
+                        MeshMaterial2d(materials.add(diplopod_color(head.player))),
+                    ))
+                    .insert(DiplopodSegment)
+                    .insert(*pos)
+                    .insert(PreviousPosition(*pos))
+                    .insert(GridLayer::Diplopod)
+                    .insert(OnGameScreen)
+                    .id(),
+            );
+        }
     }
 }
 
-pub fn movement(
-    mut heads: Query<(Entity, &DiplopodHead)>,
-    mut positions: Query<&mut Position, With<DiplopodSegment>>,
-    segments: ResMut<DiplopodSegments>,
-    mut last_tail_position: ResMut<LastTailPosition>,
-    mut game_over_writer: EventWriter<GameOver>,
+/// Despawns the segments the simulation removed from the tails of the diplopods.
+pub fn shrink(
+    mut commands: Commands,
+    sim: Res<GameSim>,
+    mut heads: Query<(&DiplopodHead, &mut DiplopodSegments)>,
+    mut shrink_reader: EventReader<Shrink>,
 ) {
-    let Some((head_entity, head)) = heads.iter_mut().next() else {
+    if shrink_reader.read().next().is_none() {
         return;
-    };
+    }
 
-    let segment_positions = segments
-        .0
-        .iter()
-        .map(|e| *positions.get_mut(*e).unwrap())
-        .collect::<Vec<Position>>();
+    for (head, mut segments) in heads.iter_mut() {
+        while segments.0.len() > sim.player_segments(head.player).len() {
+            if let Some(segment) = segments.0.pop() {
+                commands.entity(segment).despawn();
+            }
+        }
+    }
+}
 
-    let mut head_pos = positions.get_mut(head_entity).unwrap();
-    head_pos.x += head.direction.x as i32;
-    head_pos.y += head.direction.y as i32;
+/// Moves the segment entities to the positions of the simulated segments.
+pub fn movement(
+    sim: Res<GameSim>,
+    heads: Query<(&DiplopodHead, &DiplopodSegments)>,
+    mut positions: Query<&mut Position, With<DiplopodSegment>>,
+) {
+    for (head, segments) in heads.iter() {
+        for (segment, pos) in segments.0.iter().zip(sim.player_segments(head.player)) {
+            if let Ok(mut position) = positions.get_mut(*segment) {
+                *position = *pos;
+            }
+        }
+    }
+}
 
-    if segment_positions.contains(&head_pos) && (head.direction.x != 0.0 || head.direction.y != 0.0)
-    {
-        game_over_writer.write(GameOver);
+/// Rebuilds the head sprite and the meshes of the segments for a changed tile size.
+fn resize(
+    tile_size: Res<TileSize>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut heads: Query<&mut Sprite, With<DiplopodHead>>,
+    mut segments: Query<&mut Mesh2d, With<DiplopodSegment>>,
+) {
+    for mut sprite in heads.iter_mut() {
+        sprite.custom_size = Some(Vec2::splat(tile_size.0 as f32));
     }
 
-    segment_positions
-        .iter()
-        .zip(segments.0.iter().skip(1))
-        .for_each(|(pos, segment)| {
-            *positions.get_mut(*segment).unwrap() = *pos;
-        });
+    let size = (tile_size.0 - 4) as f32;
+    let mesh = meshes.add(Rectangle::new(size, size));
 
-    last_tail_position.0 = Some(*segment_positions.last().unwrap());
+    for mut mesh2d in segments.iter_mut() {
+        mesh2d.0 = mesh.clone();
+    }
 }
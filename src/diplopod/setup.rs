@@ -1,30 +1,29 @@
-use super::{DIPLOPOD_COLOR, DiplopodHead, DiplopodSegment, DiplopodSegments};
+use super::{DiplopodHead, DiplopodSegment, DiplopodSegments, diplopod_color};
 use crate::{
-    components::{OnGameScreen, Position},
+    components::{GridLayer, OnGameScreen, PreviousPosition},
     resources::TileSize,
+    simulation::GameSim,
 };
 use bevy::prelude::*;
 
-pub fn init(
-    mut commands: Commands,
-    mut segments: ResMut<DiplopodSegments>,
-    tile_size: Res<TileSize>,
-) {
-    segments.0 = vec![
-        commands
+pub fn init(mut commands: Commands, sim: Res<GameSim>, tile_size: Res<TileSize>) {
+    for player in 0..sim.players() {
+        let head = sim.player_segments(player)[0];
+        let entity = commands
             .spawn((
-                Sprite::from_color(DIPLOPOD_COLOR, Vec2::splat(tile_size.0 as f32)),
+                Sprite::from_color(diplopod_color(player), Vec2::splat(tile_size.0 as f32)),
                 Transform::default(),
             ))
-            .insert(DiplopodHead {
-                direction: Vec2::ZERO,
-            })
+            .insert(DiplopodHead { player })
             .insert(DiplopodSegment)
-            .insert(Position {
-                x: crate::ARENA_WIDTH / 2,
-                y: crate::ARENA_HEIGHT / 2,
-            })
+            .insert(head)
+            .insert(PreviousPosition(head))
+            .insert(GridLayer::Diplopod)
             .insert(OnGameScreen)
-            .id(),
-    ];
+            .id();
+
+        commands
+            .entity(entity)
+            .insert(DiplopodSegments(vec![entity]));
+    }
 }
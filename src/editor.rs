@@ -0,0 +1,688 @@
+use crate::{
+    GameState,
+    components::{GridLayer, Position},
+    controls::{self, Controls},
+    diplopod::DIPLOPOD_COLOR,
+    food::{AMOUNT_OF_FOOD, FOOD_COLOR},
+    graphics::world_to_grid,
+    maze::{Maze, Mazes, SelectedMaze, UserMazes},
+    resources::{ArenaSize, ShownArena, TileSize},
+    wall::{WALL_COLOR, wall_bundle},
+};
+use bevy::{
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+/// Adds an editor in which mazes are built tile by tile, test played and saved.
+pub struct EditorPlugin;
+
+const HELP_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const STATUS_COLOR: Color = Color::WHITE;
+const CURSOR_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.4);
+const ZONE_ALPHA: f32 = 0.3;
+const MAX_NAME_LENGTH: usize = 24;
+
+const KEYBOARD_HELP: &str = "Directions move | X wall | F food zone | R start and direction | -/+ food | T or pause test | N name | Confirm save | Back menu";
+const GAMEPAD_HELP: &str = "Directions move | X wall | Y start and direction | Select food zone | LT/RT food | Pause test | Confirm save | Back menu";
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Editor), setup_editor)
+            .add_systems(
+                Update,
+                (
+                    (keyboard, gamepad, mouse),
+                    (
+                        spawn_tiles.run_if(
+                            resource_exists_and_changed::<EditedMaze>
+                                .or(resource_changed::<TileSize>),
+                        ),
+                        update_cursor.run_if(resource_exists_and_changed::<Cursor>),
+                        update_status.run_if(
+                            resource_exists_and_changed::<EditedMaze>
+                                .or(resource_exists_and_changed::<Status>),
+                        ),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Editor))
+                    .run_if(resource_exists::<EditedMaze>),
+            )
+            .add_systems(
+                OnExit(GameState::Editor),
+                crate::despawn_screen::<OnEditorScreen>,
+            );
+    }
+}
+
+#[derive(Component)]
+struct OnEditorScreen;
+
+/// A tile of the edited maze that is rebuilt whenever the maze changes.
+#[derive(Component)]
+struct EditorTile;
+
+#[derive(Component)]
+struct CursorTile;
+
+#[derive(Component)]
+struct StatusText;
+
+/// The maze that is edited and the maze that was chosen in the menu before.
+#[derive(Resource)]
+struct EditedMaze {
+    maze: Maze,
+    selection: Option<Handle<Maze>>,
+    /// The asset the maze is test played with, replaced by every test.
+    test_maze: Option<Handle<Maze>>,
+}
+
+/// The tile that is changed by the next action.
+#[derive(Resource, PartialEq)]
+struct Cursor(Position);
+
+/// Result of the last action, e.g. why the maze can't be played.
+#[derive(Default, Resource)]
+struct Status {
+    message: String,
+    /// Whether saving again replaces another maze that was saved with the same name.
+    overwrite: bool,
+    /// The new name of the maze while it is typed.
+    name: Option<String>,
+}
+
+/// Exists while a maze of the editor is played, instead of the maze chosen in the menu. The game
+/// returns to the editor afterwards.
+#[derive(Resource)]
+pub struct TestPlay(pub Handle<Maze>);
+
+impl EditedMaze {
+    fn toggle_wall(&mut self, pos: Position) -> Result<(), String> {
+        if pos == self.maze.start {
+            return Err("The start can't be a wall".to_string());
+        }
+
+        if let Some(index) = self.maze.walls.iter().position(|wall| *wall == pos) {
+            self.maze.walls.remove(index);
+        } else {
+            self.maze.zones.retain(|zone| *zone != pos);
+            self.maze.walls.push(pos);
+        }
+
+        Ok(())
+    }
+
+    fn toggle_zone(&mut self, pos: Position) {
+        if let Some(index) = self.maze.zones.iter().position(|zone| *zone == pos) {
+            self.maze.zones.remove(index);
+        } else {
+            self.maze.walls.retain(|wall| *wall != pos);
+            self.maze.zones.push(pos);
+        }
+    }
+
+    /// Moves the start to the position or turns the start direction if it is there already.
+    fn set_start(&mut self, pos: Position) {
+        if self.maze.start == pos {
+            self.maze.direction = match (self.maze.direction.x, self.maze.direction.y) {
+                (0, 0) => IVec2::X,
+                (1, 0) => IVec2::NEG_Y,
+                (0, -1) => IVec2::NEG_X,
+                (-1, 0) => IVec2::Y,
+                _ => IVec2::ZERO,
+            };
+        } else {
+            self.maze.walls.retain(|wall| *wall != pos);
+            self.maze.start = pos;
+        }
+    }
+
+    fn change_food(&mut self, delta: i32) {
+        self.maze.food = self.maze.food.saturating_add_signed(delta).max(1);
+    }
+}
+
+/// An action of the player in the editor.
+enum Action {
+    Move(IVec2),
+    Wall,
+    Zone,
+    Start,
+    Food(i32),
+    Test,
+    Name,
+    Save,
+    Back,
+}
+
+/// The resources that are needed to perform the actions.
+#[derive(bevy::ecs::system::SystemParam)]
+struct Actions<'w, 's> {
+    commands: Commands<'w, 's>,
+    edited: ResMut<'w, EditedMaze>,
+    cursor: ResMut<'w, Cursor>,
+    status: ResMut<'w, Status>,
+    assets: ResMut<'w, Assets<Maze>>,
+    mazes: ResMut<'w, Mazes>,
+    user_mazes: ResMut<'w, UserMazes>,
+    selected_maze: ResMut<'w, SelectedMaze>,
+    game_state: ResMut<'w, NextState<GameState>>,
+}
+
+impl Actions<'_, '_> {
+    fn perform(&mut self, action: Action) {
+        let pos = self.cursor.0;
+
+        if self.status.overwrite && !matches!(action, Action::Save) {
+            *self.status = Status::default();
+        }
+
+        match action {
+            Action::Move(direction) => {
+                let next = Position {
+                    x: (pos.x + direction.x).clamp(0, self.edited.maze.width - 1),
+                    y: (pos.y + direction.y).clamp(0, self.edited.maze.height - 1),
+                };
+                self.cursor.set_if_neq(Cursor(next));
+            }
+            Action::Wall => {
+                if let Err(e) = self.edited.toggle_wall(pos) {
+                    self.status.message = e;
+                }
+            }
+            Action::Zone => self.edited.toggle_zone(pos),
+            Action::Start => self.edited.set_start(pos),
+            Action::Food(delta) => self.edited.change_food(delta),
+            Action::Test => self.test(),
+            Action::Name => self.status.name = Some(self.edited.maze.name.clone()),
+            Action::Save => self.save(),
+            Action::Back => self.back(),
+        }
+    }
+
+    /// Plays the maze right away. The campaign and the versus mode are replaced by the classic
+    /// mode, because they aren't played in the chosen maze. The choices of the menu are kept.
+    fn test(&mut self) {
+        if let Err(e) = self.edited.maze.validate() {
+            self.status.message = e.to_string();
+            return;
+        }
+
+        let maze = self.edited.maze.clone();
+        let handle = match &self.edited.test_maze {
+            Some(handle) => {
+                self.assets.insert(handle, maze);
+                handle.clone()
+            }
+            None => self.assets.add(maze),
+        };
+        self.edited.test_maze = Some(handle.clone());
+        self.commands.insert_resource(TestPlay(handle));
+        self.game_state.set(GameState::Game);
+    }
+
+    /// Types the name of the maze. Confirm renames the maze, unless the name is empty or the one
+    /// of a built-in maze, and back keeps the old name.
+    fn type_name(&mut self, event: &KeyboardInput, controls: &Controls) {
+        let Some(name) = &mut self.status.name else {
+            return;
+        };
+
+        if controls
+            .keys(controls::Action::Confirm)
+            .contains(&event.key_code)
+        {
+            let name = name.trim().to_string();
+            if name.is_empty() {
+                self.status.message = "The name can't be empty".to_string();
+            } else if self.mazes.is_built_in_name(&name, &self.assets) {
+                self.status.message = format!("{} is the name of a built-in maze", name);
+            } else {
+                self.edited.maze.name = name;
+                self.status.message.clear();
+                self.status.name = None;
+            }
+            return;
+        }
+
+        if controls
+            .keys(controls::Action::Back)
+            .contains(&event.key_code)
+        {
+            self.status.name = None;
+            return;
+        }
+
+        match &event.logical_key {
+            Key::Backspace => {
+                name.pop();
+            }
+            Key::Space if name.len() < MAX_NAME_LENGTH => name.push(' '),
+            Key::Character(text) => {
+                for c in text.chars() {
+                    if (c.is_ascii_alphanumeric() || c == '-') && name.len() < MAX_NAME_LENGTH {
+                        name.push(c);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Saves the maze. Replacing another maze that was saved with the same name has to be
+    /// confirmed by saving again. The names of the built-in mazes are reserved for them.
+    fn save(&mut self) {
+        if let Err(e) = self.edited.maze.validate() {
+            self.status.message = e.to_string();
+            return;
+        }
+
+        if self
+            .mazes
+            .is_built_in_name(&self.edited.maze.name, &self.assets)
+        {
+            self.status.message = format!(
+                "{} is the name of a built-in maze, rename it to save it",
+                self.edited.maze.name
+            );
+            return;
+        }
+
+        let replaces_other = self
+            .user_mazes
+            .saved(&self.edited.maze.name)
+            .is_some_and(|saved| self.edited.selection.as_ref() != Some(saved));
+        if replaces_other && !self.status.overwrite {
+            self.status.message = format!(
+                "{} exists already, save again to replace it",
+                self.edited.maze.name
+            );
+            self.status.overwrite = true;
+            return;
+        }
+        self.status.overwrite = false;
+
+        match self
+            .user_mazes
+            .save(self.edited.maze.clone(), &mut self.assets, &mut self.mazes)
+        {
+            Ok(handle) => {
+                self.edited.selection = Some(handle);
+                self.status.message = format!("Saved {}", self.edited.maze.name);
+            }
+            Err(e) => {
+                warn!("{}", e);
+                self.status.message = format!("Unable to save the maze: {}", e);
+            }
+        }
+    }
+
+    /// Returns to the menu with the maze selected that was saved last or chosen before.
+    fn back(&mut self) {
+        self.selected_maze.0 = self.edited.selection.clone();
+        self.commands.remove_resource::<EditedMaze>();
+        self.commands.remove_resource::<Cursor>();
+        self.commands.remove_resource::<Status>();
+        self.game_state.set(GameState::Menu);
+    }
+}
+
+/// The actions of the editor that are performed with the actions of the controls. The second
+/// directions move the cursor as well.
+const CONTROLS: [(controls::Action, Action); 7] = [
+    (controls::Action::Up, Action::Move(IVec2::Y)),
+    (controls::Action::Down, Action::Move(IVec2::NEG_Y)),
+    (controls::Action::Left, Action::Move(IVec2::NEG_X)),
+    (controls::Action::Right, Action::Move(IVec2::X)),
+    (controls::Action::Pause, Action::Test),
+    (controls::Action::Confirm, Action::Save),
+    (controls::Action::Back, Action::Back),
+];
+
+/// Performs the actions of the controls and of the keys of the editor, which are ignored while
+/// they are bound in the controls. While the name is typed, the keys only change it.
+fn keyboard(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    controls: Res<Controls>,
+    mut actions: Actions,
+) {
+    if actions.status.name.is_some() {
+        for event in keyboard_events.read() {
+            if event.state == ButtonState::Pressed {
+                actions.type_name(event, &controls);
+            }
+        }
+        return;
+    }
+    keyboard_events.clear();
+
+    for (action, editor_action) in CONTROLS {
+        if controls.just_pressed(action, &keyboard_input) {
+            actions.perform(editor_action);
+        }
+    }
+
+    let bindings = [
+        (KeyCode::KeyX, Action::Wall),
+        (KeyCode::KeyF, Action::Zone),
+        (KeyCode::KeyR, Action::Start),
+        (KeyCode::Minus, Action::Food(-1)),
+        (KeyCode::Equal, Action::Food(1)),
+        (KeyCode::KeyT, Action::Test),
+        (KeyCode::KeyN, Action::Name),
+    ];
+
+    for (key, action) in bindings {
+        if keyboard_input.just_pressed(key) && !controls.is_key_bound(key) {
+            actions.perform(action);
+        }
+    }
+}
+
+/// Works like the keyboard with the buttons of the gamepads, except for typing the name, which
+/// can only be cancelled.
+fn gamepad(gamepads: Query<&Gamepad>, controls: Res<Controls>, mut actions: Actions) {
+    for gamepad in gamepads.iter() {
+        if actions.status.name.is_some() {
+            if controls.gamepad_just_pressed(controls::Action::Back, gamepad) {
+                actions.status.name = None;
+            }
+            continue;
+        }
+
+        for (action, editor_action) in CONTROLS {
+            if controls.gamepad_just_pressed(action, gamepad) {
+                actions.perform(editor_action);
+            }
+        }
+
+        let bindings = [
+            (GamepadButton::West, Action::Wall),
+            (GamepadButton::Select, Action::Zone),
+            (GamepadButton::North, Action::Start),
+            (GamepadButton::LeftTrigger, Action::Food(-1)),
+            (GamepadButton::RightTrigger, Action::Food(1)),
+        ];
+
+        for (button, action) in bindings {
+            if gamepad.just_pressed(button) && !controls.is_button_bound(button) {
+                actions.perform(action);
+            }
+        }
+    }
+}
+
+/// Moves the cursor to the tile under the mouse pointer. The left button toggles a wall, the
+/// right button a food zone and the middle button sets the start.
+fn mouse(
+    mut cursor_moved: EventReader<CursorMoved>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    tile_size: Res<TileSize>,
+    mut actions: Actions,
+) {
+    let moved = cursor_moved.read().last().is_some();
+    if actions.status.name.is_some() {
+        return;
+    }
+    let clicked = buttons.get_just_pressed().next().is_some();
+    if !moved && !clicked {
+        return;
+    }
+
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.single(), cameras.single()) else {
+        return;
+    };
+    let Some(world) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    let maze = &actions.edited.maze;
+    let pos = world_to_grid(world, maze.size(), &tile_size);
+    if !maze.contains(&pos) {
+        return;
+    }
+
+    actions.cursor.set_if_neq(Cursor(pos));
+
+    if buttons.just_pressed(MouseButton::Left) {
+        actions.perform(Action::Wall);
+    }
+
+    if buttons.just_pressed(MouseButton::Right) {
+        actions.perform(Action::Zone);
+    }
+
+    if buttons.just_pressed(MouseButton::Middle) {
+        actions.perform(Action::Start);
+    }
+}
+
+/// Rebuilds the walls, the food zones, the start and the cursor of the edited maze.
+fn spawn_tiles(
+    mut commands: Commands,
+    edited: Res<EditedMaze>,
+    cursor: Res<Cursor>,
+    tile_size: Res<TileSize>,
+    tiles: Query<Entity, With<EditorTile>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for tile in tiles.iter() {
+        commands.entity(tile).despawn();
+    }
+
+    let size = tile_size.0 as f32;
+    let mesh = meshes.add(Rectangle::new(size, size));
+    let wall_color = materials.add(WALL_COLOR);
+    let zone_color = materials.add(FOOD_COLOR.with_alpha(ZONE_ALPHA));
+
+    for pos in &edited.maze.walls {
+        commands.spawn((
+            wall_bundle(*pos, mesh.clone(), wall_color.clone()),
+            EditorTile,
+            OnEditorScreen,
+        ));
+    }
+
+    for pos in &edited.maze.zones {
+        commands.spawn((
+            Mesh2d(mesh.clone()),
+            MeshMaterial2d(zone_color.clone()),
+            *pos,
+            GridLayer::Food,
+            EditorTile,
+            OnEditorScreen,
+        ));
+    }
+
+    commands.spawn((
+        Sprite::from_color(DIPLOPOD_COLOR, Vec2::splat(size)),
+        edited.maze.start,
+        GridLayer::Diplopod,
+        EditorTile,
+        OnEditorScreen,
+    ));
+
+    commands.spawn((
+        Sprite::from_color(CURSOR_COLOR, Vec2::splat(size)),
+        cursor.0,
+        GridLayer::Message,
+        CursorTile,
+        EditorTile,
+        OnEditorScreen,
+    ));
+}
+
+fn update_cursor(cursor: Res<Cursor>, mut query: Query<&mut Position, With<CursorTile>>) {
+    for mut pos in query.iter_mut() {
+        *pos = cursor.0;
+    }
+}
+
+fn update_status(
+    edited: Res<EditedMaze>,
+    status: Res<Status>,
+    mut query: Query<&mut Text, With<StatusText>>,
+) {
+    for mut text in query.iter_mut() {
+        text.0 = status_text(&edited, &status);
+    }
+}
+
+fn status_text(edited: &EditedMaze, status: &Status) -> String {
+    if let Some(name) = &status.name {
+        return format!(
+            "Name: {}_ | Confirm renames, back cancels | {}",
+            name, status.message
+        );
+    }
+
+    let direction = match (edited.maze.direction.x, edited.maze.direction.y) {
+        (0, 1) => "up",
+        (0, -1) => "down",
+        (-1, 0) => "left",
+        (1, 0) => "right",
+        _ => "none",
+    };
+
+    format!(
+        "{} {} | Direction {} | Food {} | {}",
+        edited.maze.name,
+        edited.maze.size(),
+        direction,
+        edited.maze.food,
+        status.message
+    )
+}
+
+/// Returns an arena of the size with walls on its border and the start in the center.
+fn empty_maze(name: String, arena_size: ArenaSize) -> Maze {
+    let mut walls = Vec::new();
+    for x in 0..arena_size.width {
+        walls.push(Position { x, y: 0 });
+        walls.push(Position {
+            x,
+            y: arena_size.height - 1,
+        });
+    }
+    for y in 1..arena_size.height - 1 {
+        walls.push(Position { x: 0, y });
+        walls.push(Position {
+            x: arena_size.width - 1,
+            y,
+        });
+    }
+
+    Maze {
+        name,
+        width: arena_size.width,
+        height: arena_size.height,
+        walls,
+        zones: Vec::new(),
+        start: Position {
+            x: arena_size.width / 2,
+            y: arena_size.height / 2,
+        },
+        direction: IVec2::ZERO,
+        food: AMOUNT_OF_FOOD,
+    }
+}
+
+/// Starts to edit the maze that is selected in the menu or an empty arena of the chosen size.
+/// After a test the maze that was played is edited further.
+fn setup_editor(
+    mut commands: Commands,
+    edited: Option<Res<EditedMaze>>,
+    selected_maze: Res<SelectedMaze>,
+    mazes: Res<Assets<Maze>>,
+    user_mazes: Res<UserMazes>,
+    arena_size: Res<ArenaSize>,
+    mut arena: ResMut<ShownArena>,
+) {
+    commands.remove_resource::<TestPlay>();
+
+    let status = Status::default();
+    let edited = match edited {
+        Some(edited) => EditedMaze {
+            maze: edited.maze.clone(),
+            selection: edited.selection.clone(),
+            test_maze: edited.test_maze.clone(),
+        },
+        None => {
+            let maze = selected_maze
+                .0
+                .as_ref()
+                .and_then(|handle| mazes.get(handle))
+                .cloned()
+                .unwrap_or_else(|| empty_maze(user_mazes.unused_name(), *arena_size));
+            commands.insert_resource(Cursor(maze.start));
+
+            EditedMaze {
+                maze,
+                selection: selected_maze.0.clone(),
+                test_maze: None,
+            }
+        }
+    };
+
+    arena.set_if_neq(ShownArena(edited.maze.size()));
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(5.0)),
+                ..default()
+            },
+            OnEditorScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for help in [KEYBOARD_HELP, GAMEPAD_HELP] {
+                        parent.spawn((
+                            Text::new(help),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(HELP_COLOR),
+                        ));
+                    }
+                });
+
+            parent.spawn((
+                Text::new(status_text(&edited, &status)),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(STATUS_COLOR),
+                StatusText,
+            ));
+        });
+
+    // inserting the maze rebuilds the tiles
+    commands.insert_resource(edited);
+    commands.insert_resource(status);
+}
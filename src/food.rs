@@ -2,63 +2,132 @@ mod setup;
 pub mod spawn;
 
 use crate::{
-    ARENA_HEIGHT, ARENA_WIDTH,
+    campaign::LevelCompleted,
     components::{GameState, Phase, Position},
     resources::TileSize,
+    simulation::{FoodKind, GameSim},
 };
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 
 pub const AMOUNT_OF_FOOD: u32 = 16;
+pub const AMOUNT_OF_POISON: u32 = 6;
+/// An antidote is placed after this many food were eaten.
+pub const ANTIDOTE_INTERVAL: u32 = 10;
+/// Number of ticks an eaten antidote turns poison into normal food.
+pub const ANTIDOTE_DURATION: u64 = 100;
+/// Number of ticks between two appearances of the super food.
+pub const SUPER_FOOD_INTERVAL: u64 = 200;
+/// Number of ticks the super food stays in the arena.
+pub const SUPER_FOOD_DURATION: u64 = 80;
+/// Number of ticks before the super food disappears in which it blinks.
+pub const SUPER_FOOD_BLINK_DURATION: u64 = 24;
+/// Number of segments the diplopod grows when it eats the super food.
+pub const SUPER_FOOD_GROWTH: u8 = 3;
 pub const FOOD_COLOR: Color = Color::srgb(0.0, 1.0, 0.0);
+pub const POISON_COLOR: Color = Color::srgb(0.5, 0.0, 1.0);
+pub const ANTIDOTE_COLOR: Color = Color::srgb(0.0, 0.75, 1.0);
+pub const SUPER_FOOD_COLOR: Color = Color::srgb(1.0, 0.85, 0.0);
 
 #[derive(Component)]
-pub struct Food;
+pub struct Food(pub FoodKind);
 
 #[derive(Event)]
-pub struct SpawnFood;
+pub struct SpawnFood(pub Position, pub FoodKind);
+
+/// Food of the given kind was eaten at the given position, which was worth the given points,
+/// including the combo.
+#[derive(Event)]
+pub struct FoodEaten(pub Position, pub FoodKind, pub u16);
+
+/// Returns the color food of the given kind is rendered with.
+pub fn food_color(kind: FoodKind) -> Color {
+    match kind {
+        FoodKind::Normal => FOOD_COLOR,
+        FoodKind::Poison => POISON_COLOR,
+        FoodKind::Antidote => ANTIDOTE_COLOR,
+        FoodKind::Super => SUPER_FOOD_COLOR,
+    }
+}
 
 pub struct FoodPlugin;
 
 impl Plugin for FoodPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Game), setup::init)
-            .add_systems(
-                Update,
-                (position_translation,)
-                    .after(Phase::Movement)
+        app.add_systems(
+            OnEnter(GameState::Game),
+            setup::init.after(crate::simulation::setup::init),
+        )
+        .add_systems(
+            Update,
+            (
+                (
+                    antidote.run_if(resource_exists_and_changed::<GameSim>),
+                    blink.run_if(resource_exists_and_changed::<GameSim>),
+                    (crate::despawn_screen::<Food>, setup::init)
+                        .chain()
+                        .run_if(on_event::<LevelCompleted>),
+                )
                     .run_if(in_state(GameState::Game)),
-            );
+                resize.run_if(resource_changed::<TileSize>),
+            )
+                .chain()
+                .after(Phase::Movement),
+        );
     }
 }
 
-fn position_translation(
-    windows: Query<&Window, With<PrimaryWindow>>,
-    mut q: Query<(&Position, &mut Transform), With<Food>>,
-    tile_size: Res<TileSize>,
+/// Renders poison like normal food while an antidote is active.
+fn antidote(
+    sim: Res<GameSim>,
+    food: Query<(&Food, &MeshMaterial2d<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    fn convert(pos: f32, bound_window: f32, bound_game: f32, tile_size: f32) -> f32 {
-        pos / bound_game * bound_window - (bound_window / 2.) + (tile_size / 2.)
+    let poison_color = if sim.is_antidote_active() {
+        FOOD_COLOR
+    } else {
+        POISON_COLOR
+    };
+
+    for (food, material) in food.iter() {
+        if food.0 != FoodKind::Poison {
+            continue;
+        }
+
+        let outdated = materials
+            .get(&material.0)
+            .is_some_and(|material| material.color != poison_color);
+
+        if outdated && let Some(material) = materials.get_mut(&material.0) {
+            material.color = poison_color;
+        }
     }
+}
 
-    let Ok(window) = windows.single() else {
-        return;
+/// Lets the super food blink shortly before it disappears.
+fn blink(sim: Res<GameSim>, mut food: Query<(&Food, &mut Visibility)>) {
+    let ticks = sim.super_food_ticks();
+    let visibility = if ticks > SUPER_FOOD_BLINK_DURATION || (ticks / 2).is_multiple_of(2) {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
     };
 
-    for (pos, mut transform) in q.iter_mut() {
-        transform.translation = Vec3::new(
-            convert(
-                pos.x as f32,
-                window.width(),
-                ARENA_WIDTH as f32,
-                tile_size.0 as f32,
-            ),
-            convert(
-                pos.y as f32,
-                window.height(),
-                ARENA_HEIGHT as f32,
-                tile_size.0 as f32,
-            ),
-            1.0,
-        );
+    for (food, mut food_visibility) in food.iter_mut() {
+        if food.0 == FoodKind::Super {
+            food_visibility.set_if_neq(visibility);
+        }
+    }
+}
+
+/// Rebuilds the mesh of the food for a changed tile size.
+fn resize(
+    tile_size: Res<TileSize>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut q: Query<&mut Mesh2d, With<Food>>,
+) {
+    let mesh = meshes.add(Rectangle::new(tile_size.0 as f32, tile_size.0 as f32));
+
+    for mut mesh2d in q.iter_mut() {
+        mesh2d.0 = mesh.clone();
     }
 }
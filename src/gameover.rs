@@ -1,7 +1,12 @@
 use crate::{
     components::{GameState, Phase},
-    diplopod::DiplopodSegment,
-    resources::{FreePositions, Highscore, Lastscore},
+    editor::TestPlay,
+    highscore::{DEFAULT_INITIALS, unix_timestamp},
+    level::Level,
+    maze::{PlayedMaze, UserMazes},
+    replay::{self, Playback, Recording, Replay},
+    resources::{ArenaSize, Lastscore, Leaderboard, LeaderboardEntry, Playtime, Score},
+    simulation::{GameMode, GameSim},
 };
 use bevy::prelude::*;
 
@@ -23,27 +28,81 @@ impl Plugin for GameOverPlugin {
 }
 
 fn game_over(
+    mut commands: Commands,
     mut reader: EventReader<GameOver>,
-    segments: Query<Entity, With<DiplopodSegment>>,
-    mut free_positions: ResMut<FreePositions>,
+    sim: Res<GameSim>,
+    score: Res<Score>,
+    playtime: Res<Playtime>,
+    level: Res<Level>,
+    played_maze: Res<PlayedMaze>,
+    user_mazes: Res<UserMazes>,
+    recording: Res<Recording>,
+    playback: Option<Res<Playback>>,
+    test_play: Option<Res<TestPlay>>,
     mut game_state: ResMut<NextState<GameState>>,
     mut lastscore: ResMut<Lastscore>,
-    mut highscore: ResMut<Highscore>,
+    mut leaderboard: ResMut<Leaderboard>,
 ) {
     if reader.read().next().is_none() {
         return;
     };
 
-    lastscore.0 = 0;
-    for _ in segments.iter() {
-        lastscore.0 += 1;
+    if playback.is_some() {
+        commands.remove_resource::<Playback>();
+        game_state.set(GameState::Replays);
+        return;
     }
 
-    if lastscore.0 > highscore.0 {
-        highscore.0 = lastscore.0;
+    if sim.mode() == GameMode::Versus {
+        game_state.set(GameState::Winner);
+        return;
     }
 
-    free_positions.reset();
+    // the editor removes the test play when it is shown again
+    if test_play.is_some() {
+        game_state.set(GameState::Editor);
+        return;
+    }
 
-    game_state.set(GameState::Highscore);
+    lastscore.0 = score.0;
+
+    // a game without a single point is neither kept nor ranked
+    if score.0 == 0 {
+        leaderboard.latest = None;
+        game_state.set(GameState::Highscore);
+        return;
+    }
+
+    let replay = Replay {
+        seed: sim.seed(),
+        score: lastscore.0,
+        arena: ArenaSize::new(sim.width(), sim.height()),
+        level: level.starting,
+        mode: sim.mode(),
+        maze: played_maze
+            .0
+            .as_ref()
+            .and_then(|maze| user_mazes.path(maze)),
+        campaign: sim.campaign_start().unwrap_or(0),
+        inputs: recording.0.clone(),
+    };
+    if let Err(e) = replay::write_replay_to_file(&replay) {
+        warn!("{}", e);
+    }
+
+    leaderboard.insert(LeaderboardEntry {
+        initials: DEFAULT_INITIALS.to_string(),
+        score: score.0,
+        length: sim.segments().len() as u16,
+        duration: playtime.0.as_secs(),
+        seed: sim.seed(),
+        timestamp: unix_timestamp(),
+        mode: sim.mode(),
+    });
+
+    if leaderboard.latest.is_some() {
+        game_state.set(GameState::NameEntry);
+    } else {
+        game_state.set(GameState::Highscore);
+    }
 }
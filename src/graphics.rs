@@ -1,9 +1,31 @@
-use crate::{GameState, OnGameScreen, components::*, events::ShowMessage, resources::Paused};
+use crate::{
+    GameState, OnGameScreen,
+    components::*,
+    events::ShowMessage,
+    food::FoodEaten,
+    resources::{ArenaSize, Paused, ShownArena, TileSize},
+    simulation::FoodKind,
+};
 use bevy::{prelude::*, time::common_conditions::on_timer};
 use std::time::Duration;
 
 pub struct GraphicsPlugin;
 
+/// Whether moving entities glide between the tiles or snap to the next tile every tick, stored in
+/// the config.
+#[derive(Clone, Copy, Debug, PartialEq, Resource)]
+pub struct GraphicsSettings {
+    pub interpolation: bool,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            interpolation: true,
+        }
+    }
+}
+
 impl Plugin for GraphicsPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
@@ -11,7 +33,10 @@ impl Plugin for GraphicsPlugin {
             (fade_text.run_if(on_timer(Duration::from_millis(200))),)
                 .run_if(in_state(GameState::Game))
                 .run_if(not(resource_exists::<Paused>)),
-        );
+        )
+        .add_systems(FixedPreUpdate, store_previous_positions)
+        .add_systems(Update, position_translation.after(Phase::Movement))
+        .init_resource::<GraphicsSettings>();
     }
 }
 
@@ -30,24 +55,121 @@ fn fade_text(
     }
 }
 
+/// Remembers the positions before the next tick, so that the movement during the tick can be
+/// interpolated. Runs while the game is paused, too, so that paused entities stand still.
+fn store_previous_positions(mut q: Query<(&Position, &mut PreviousPosition)>) {
+    for (pos, mut previous) in q.iter_mut() {
+        previous.0 = *pos;
+    }
+}
+
+/// Places every entity with a position on its tile of the arena and on the z-order of its layer.
+/// Entities that remember their previous position are moved smoothly between the two tiles by the
+/// fraction of the tick that has already passed.
+fn position_translation(
+    mut q: Query<(
+        &Position,
+        Option<&PreviousPosition>,
+        &GridLayer,
+        &mut Transform,
+    )>,
+    tile_size: Res<TileSize>,
+    arena: Res<ShownArena>,
+    settings: Res<GraphicsSettings>,
+    time: Res<Time<Fixed>>,
+) {
+    for (pos, previous, layer, mut transform) in q.iter_mut() {
+        let mut grid_pos = Vec2::new(pos.x as f32, pos.y as f32);
+
+        // only neighbouring tiles are interpolated, everything else would slide across the arena
+        if settings.interpolation
+            && let Some(PreviousPosition(previous)) = previous
+            && (previous.x - pos.x).abs() + (previous.y - pos.y).abs() == 1
+        {
+            let previous = Vec2::new(previous.x as f32, previous.y as f32);
+            grid_pos = previous.lerp(grid_pos, time.overstep_fraction());
+        }
+
+        transform.translation = grid_to_world(grid_pos, arena.0, &tile_size).extend(layer.z());
+    }
+}
+
+/// Returns the center of the tile at the grid position in world coordinates. The arena is centered
+/// on the origin.
+pub fn grid_to_world(grid_pos: Vec2, arena_size: ArenaSize, tile_size: &TileSize) -> Vec2 {
+    let tile_size = tile_size.0 as f32;
+    let arena_size = Vec2::new(arena_size.width as f32, arena_size.height as f32);
+
+    (grid_pos - arena_size / 2.) * tile_size + tile_size / 2.
+}
+
+/// Returns the grid position of the tile at the world coordinates, the inverse of
+/// [`grid_to_world`].
+pub fn world_to_grid(world: Vec2, arena_size: ArenaSize, tile_size: &TileSize) -> Position {
+    let arena_size = Vec2::new(arena_size.width as f32, arena_size.height as f32);
+    let grid_pos = (world / tile_size.0 as f32 + arena_size / 2.).floor();
+
+    Position {
+        x: grid_pos.x as i32,
+        y: grid_pos.y as i32,
+    }
+}
+
+/// Shows what was eaten, or the points it was worth, at the tile of the food. Nothing is shown for
+/// food that wasn't worth any points, e.g. in the versus mode, which has no score.
+pub fn food_message(
+    mut food_eaten_reader: EventReader<FoodEaten>,
+    mut show_message_writer: EventWriter<ShowMessage>,
+) {
+    for FoodEaten(position, kind, points) in food_eaten_reader.read() {
+        let text = match kind {
+            FoodKind::Normal | FoodKind::Super if *points == 0 => continue,
+            FoodKind::Normal | FoodKind::Super => format!("+{}", points),
+            FoodKind::Poison => "Poisoned!".to_string(),
+            FoodKind::Antidote => "Antidote!".to_string(),
+        };
+
+        show_message_writer.write(ShowMessage {
+            text,
+            position: *position,
+        });
+    }
+}
+
 pub fn show_message(mut commands: Commands, mut show_message_reader: EventReader<ShowMessage>) {
-    let Some(show_message) = show_message_reader.read().next() else {
-        return;
-    };
-
-    commands
-        .spawn((
-            Text2d::new(&show_message.text),
-            TextFont {
-                font_size: 36.0,
-                ..default()
-            },
-            TextColor::WHITE,
-            TextLayout::new_with_justify(JustifyText::Center),
-            // ensure that the text is drawn above the diplopod
-            Transform::from_translation(Vec3::Z),
-        ))
-        .insert(show_message.position)
-        .insert(OnGameScreen)
-        .insert(FadingText(1.0));
+    for show_message in show_message_reader.read() {
+        commands
+            .spawn((
+                Text2d::new(&show_message.text),
+                TextFont {
+                    font_size: 36.0,
+                    ..default()
+                },
+                TextColor::WHITE,
+                TextLayout::new_with_justify(JustifyText::Center),
+            ))
+            .insert(show_message.position)
+            .insert(GridLayer::Message)
+            .insert(OnGameScreen)
+            .insert(FadingText(1.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_grid_positions_to_world_and_back() {
+        let arena_size = ArenaSize::new(13, 8);
+        let tile_size = TileSize(20);
+
+        for (x, y) in [(0, 0), (6, 4), (12, 7)] {
+            let world = grid_to_world(Vec2::new(x as f32, y as f32), arena_size, &tile_size);
+            assert_eq!(
+                world_to_grid(world, arena_size, &tile_size),
+                Position { x, y }
+            );
+        }
+    }
 }
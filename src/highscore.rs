@@ -1,6 +1,7 @@
 use crate::{
     GameState, TITLE,
-    resources::{self, Highscore, Lastscore},
+    resources::{Lastscore, Leaderboard, LeaderboardEntry, Seed},
+    simulation::GameMode,
 };
 use anyhow::{Result, anyhow};
 use bevy::{
@@ -13,18 +14,19 @@ use directories::ProjectDirs;
 use std::time::Duration;
 use std::{fs::File, io::Write};
 
-/// Adds a screen that shows the highscore of the current session and
-/// the score of the last game.
+/// Adds a screen that shows the leaderboard and the score of the last game.
 pub struct HighscorePlugin;
 
 const TITLE_COLOR: Color = Color::Srgba(ANTIQUE_WHITE);
 const HEADLINE_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
 const HIGHSCORE_COLOR: Color = Color::WHITE;
+const LATEST_ENTRY_COLOR: Color = Color::srgb(1.0, 0.65, 0.0);
 const INITIAL_DELAY_MILLISECONDS: u64 = 500;
 
-const QUALIFIER: &str = "com.github";
-const ORGANIZATION: &str = "tehlers";
+pub const QUALIFIER: &str = "com.github";
+pub const ORGANIZATION: &str = "tehlers";
 const HIGHSCORE: &str = "highscore";
+const LEADERBOARD: &str = "leaderboard.ron";
 
 #[derive(Component)]
 struct OnHighscoreScreen;
@@ -38,7 +40,7 @@ impl Plugin for HighscorePlugin {
             .add_systems(
                 Update,
                 (
-                    (gamepad, keyboard)
+                    (gamepad, keyboard, pointer)
                         .run_if(in_state(GameState::Highscore))
                         .run_if(not(resource_exists::<InitialDelay>)),
                     remove_initial_delay
@@ -52,65 +54,156 @@ impl Plugin for HighscorePlugin {
             )
             .add_systems(
                 Update,
-                save_highscore
-                    .run_if(resource_changed::<Highscore>.and(not(resource_added::<Highscore>))),
+                save_leaderboard.run_if(
+                    resource_changed::<Leaderboard>.and(not(resource_added::<Leaderboard>)),
+                ),
             )
-            .insert_resource(load_highscore())
+            .insert_resource(load_leaderboard())
             .init_resource::<Lastscore>();
     }
 }
 
-/// Load highscore from platform specific data directory (e.g.
-/// `$HOME/.local/share/diplopod/highscore`). If the file is invalid or inaccessible the highscore will
-/// be set back to zero.
-fn load_highscore() -> resources::Highscore {
-    match read_highscore_from_file() {
-        Ok(highscore) => Highscore(highscore),
+/// Load the leaderboard from platform specific data directory (e.g.
+/// `$HOME/.local/share/diplopod/leaderboard.ron`). If there is no leaderboard yet, the highscore
+/// of older versions is migrated. If the file is invalid or inaccessible the leaderboard will be
+/// empty.
+fn load_leaderboard() -> Leaderboard {
+    match read_leaderboard_from_file() {
+        Ok(leaderboard) => leaderboard,
         Err(e) => {
             warn!("{}", e);
-            Highscore::default()
+            Leaderboard::default()
         }
     }
 }
 
-/// Reads the highscore from the platform specific data directory and tries to parse the value as
-/// `u16`.
-fn read_highscore_from_file() -> Result<u16> {
+/// Reads the leaderboard from the platform specific data directory. Falls back to the single
+/// highscore that older versions stored in the `highscore` file.
+fn read_leaderboard_from_file() -> Result<Leaderboard> {
     if let Some(projects_dirs) = ProjectDirs::from(QUALIFIER, ORGANIZATION, TITLE) {
-        Ok(std::fs::read_to_string(projects_dirs.data_dir().join(HIGHSCORE))?.parse()?)
+        let data_dir = projects_dirs.data_dir();
+
+        match std::fs::read_to_string(data_dir.join(LEADERBOARD)) {
+            Ok(content) => Ok(ron::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                match std::fs::read_to_string(data_dir.join(HIGHSCORE)) {
+                    Ok(content) => {
+                        let leaderboard = migrate_highscore(&content)?;
+                        write_leaderboard_to_file(&leaderboard)?;
+                        Ok(leaderboard)
+                    }
+                    // a fresh install has nothing to migrate
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        Ok(Leaderboard::default())
+                    }
+                    Err(e) => Err(e.into()),
+                }
+            }
+            Err(e) => Err(e.into()),
+        }
     } else {
         Err(anyhow!(
-            "Unable to retrieve path to data directory. Highscore can't be read."
+            "Unable to retrieve path to data directory. Leaderboard can't be read."
         ))
     }
 }
 
-/// Save highscore to platform specific data directory (e.g.
-/// `$HOME/.local/share/diplopod/highscore`). Errors will be logged but otherwise ignored.
-fn save_highscore(highscore: Res<Highscore>) {
-    if let Err(e) = write_highscore_to_file(highscore.0) {
+/// Returns a leaderboard with the highscore of older versions, which were all played in the
+/// classic mode. A highscore of zero leaves the leaderboard empty.
+fn migrate_highscore(content: &str) -> Result<Leaderboard> {
+    let highscore: u16 = content.trim().parse()?;
+
+    let mut leaderboard = Leaderboard::default();
+    if highscore > 0 {
+        leaderboard.insert(LeaderboardEntry {
+            initials: DEFAULT_INITIALS.to_string(),
+            score: highscore,
+            length: 0,
+            duration: 0,
+            seed: 0,
+            timestamp: 0,
+            mode: GameMode::Classic,
+        });
+        leaderboard.latest = None;
+    }
+
+    Ok(leaderboard)
+}
+
+/// Save the leaderboard to platform specific data directory (e.g.
+/// `$HOME/.local/share/diplopod/leaderboard.ron`). Errors will be logged but otherwise ignored.
+fn save_leaderboard(leaderboard: Res<Leaderboard>) {
+    if let Err(e) = write_leaderboard_to_file(&leaderboard) {
         warn!("{}", e)
     }
 }
 
-/// Writes the highscore to the platform specific data directory. The file and all necessary
+/// Writes the leaderboard to the platform specific data directory. The file and all necessary
 /// directories are created by the function.
-fn write_highscore_to_file(highscore: u16) -> Result<()> {
+fn write_leaderboard_to_file(leaderboard: &Leaderboard) -> Result<()> {
     if let Some(projects_dirs) = ProjectDirs::from(QUALIFIER, ORGANIZATION, TITLE) {
         let data_dir = projects_dirs.data_dir();
         std::fs::create_dir_all(data_dir)?;
 
-        let mut file = File::create(data_dir.join(HIGHSCORE))?;
-        write!(file, "{}", &highscore)?;
+        let mut file = File::create(data_dir.join(LEADERBOARD))?;
+        write!(
+            file,
+            "{}",
+            ron::ser::to_string_pretty(leaderboard, ron::ser::PrettyConfig::default())?
+        )?;
 
         Ok(())
     } else {
         Err(anyhow!(
-            "Unable to retrieve path to data directory. Highscore can't be read."
+            "Unable to retrieve path to data directory. Leaderboard can't be written."
         ))
     }
 }
 
+/// Initials of entries whose player didn't enter any.
+pub const DEFAULT_INITIALS: &str = "---";
+
+/// Seconds since the Unix epoch. The web build has no access to the system clock and always
+/// returns zero.
+pub fn unix_timestamp() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        0
+    }
+}
+
+/// Formats seconds since the Unix epoch as `YYYY-MM-DD`.
+fn format_date(timestamp: u64) -> String {
+    if timestamp == 0 {
+        return "-".to_string();
+    }
+
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
 /// Removes the initial delay of the screen that ensures that keyboard and gamepad events are not processed
 /// immediately after game over.
 fn remove_initial_delay(mut commands: Commands) {
@@ -130,6 +223,18 @@ fn keyboard(
     }
 }
 
+/// Forwards to the menu when a mouse button is clicked or the screen is touched after an initial
+/// delay.
+fn pointer(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if mouse_input.get_just_released().next().is_some() || touches.any_just_released() {
+        game_state.set(GameState::Menu);
+    }
+}
+
 /// Forwards to the menu when the A key of the gamepad is pressed after an initial delay.
 pub fn gamepad(gamepads: Query<&Gamepad>, mut game_state: ResMut<NextState<GameState>>) {
     for gamepad in gamepads.iter() {
@@ -140,7 +245,13 @@ pub fn gamepad(gamepads: Query<&Gamepad>, mut game_state: ResMut<NextState<GameS
 }
 
 /// Creates the UI of the highscore screen.
-fn setup_highscore(mut commands: Commands, highscore: Res<Highscore>, lastscore: Res<Lastscore>) {
+fn setup_highscore(
+    mut commands: Commands,
+    leaderboard: Res<Leaderboard>,
+    mode: Res<GameMode>,
+    lastscore: Res<Lastscore>,
+    seed: Res<Seed>,
+) {
     commands
         .spawn((
             Node {
@@ -163,56 +274,132 @@ fn setup_highscore(mut commands: Commands, highscore: Res<Highscore>, lastscore:
                     parent.spawn((
                         Text::new(TITLE),
                         TextFont {
-                            font_size: 128.0,
+                            font_size: 96.0,
                             ..default()
                         },
                         TextColor(TITLE_COLOR),
                         Node {
-                            margin: UiRect::all(Val::Px(50.0)),
+                            margin: UiRect::all(Val::Px(20.0)),
                             ..default()
                         },
                     ));
 
                     parent.spawn((
-                        Text::new("Highscore"),
+                        Text::new(format!(
+                            "{} highscore {}",
+                            *mode,
+                            leaderboard.highscore(*mode)
+                        )),
                         TextFont {
-                            font_size: 64.0,
+                            font_size: 48.0,
                             ..default()
                         },
                         TextColor(HEADLINE_COLOR),
                         Node {
-                            margin: UiRect::all(Val::Px(50.0)),
+                            margin: UiRect::all(Val::Px(10.0)),
                             ..default()
                         },
                     ));
 
+                    spawn_leaderboard_row(
+                        parent,
+                        ["#", "Name", "Score", "Length", "Time", "Date"].map(String::from),
+                        HEADLINE_COLOR,
+                    );
+
+                    for (rank, (index, entry)) in leaderboard.entries(*mode).enumerate() {
+                        let color = if leaderboard.latest == Some(index) {
+                            LATEST_ENTRY_COLOR
+                        } else {
+                            HIGHSCORE_COLOR
+                        };
+
+                        spawn_leaderboard_row(
+                            parent,
+                            [
+                                format!("{}", rank + 1),
+                                entry.initials.clone(),
+                                format!("{}", entry.score),
+                                format!("{}", entry.length),
+                                format!("{}:{:02}", entry.duration / 60, entry.duration % 60),
+                                format_date(entry.timestamp),
+                            ],
+                            color,
+                        );
+                    }
+
                     parent.spawn((
-                        Text::new(format!("Highscore {}", &highscore.0)),
+                        Text::new(format!("Your last score was {}", &lastscore.0)),
                         TextFont {
-                            font_size: 64.0,
+                            font_size: 32.0,
                             ..default()
                         },
                         TextColor(HIGHSCORE_COLOR),
                         Node {
-                            margin: UiRect::all(Val::Px(25.0)),
+                            margin: UiRect::all(Val::Px(15.0)),
                             ..default()
                         },
                     ));
 
                     parent.spawn((
-                        Text::new(format!("Your last score was {}", &lastscore.0)),
+                        Text::new(format!("Seed {}", &seed.value)),
                         TextFont {
-                            font_size: 64.0,
-                            ..default()
-                        },
-                        TextColor(HIGHSCORE_COLOR),
-                        Node {
-                            margin: UiRect::all(Val::Px(25.0)),
+                            font_size: 24.0,
                             ..default()
                         },
+                        TextColor(HEADLINE_COLOR),
                     ));
                 });
         });
 
     commands.init_resource::<InitialDelay>();
 }
+
+/// Spawns a row of the leaderboard table with one fixed width cell per column.
+fn spawn_leaderboard_row(parent: &mut ChildSpawnerCommands, cells: [String; 6], color: Color) {
+    const CELL_WIDTHS: [f32; 6] = [50.0, 100.0, 100.0, 100.0, 100.0, 160.0];
+
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            ..default()
+        })
+        .with_children(|parent| {
+            for (cell, width) in cells.into_iter().zip(CELL_WIDTHS) {
+                parent.spawn((
+                    Text::new(cell),
+                    TextFont {
+                        font_size: 24.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                    Node {
+                        width: Val::Px(width),
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_the_highscore_of_older_versions() {
+        let leaderboard = migrate_highscore("42\n").unwrap();
+
+        assert_eq!(leaderboard.entries.len(), 1);
+        assert_eq!(leaderboard.entries[0].score, 42);
+        assert_eq!(leaderboard.entries[0].initials, DEFAULT_INITIALS);
+        assert_eq!(leaderboard.entries[0].mode, GameMode::Classic);
+        assert_eq!(leaderboard.latest, None);
+    }
+
+    #[test]
+    fn skips_an_empty_or_invalid_highscore() {
+        assert!(migrate_highscore("0").unwrap().entries.is_empty());
+        assert!(migrate_highscore("many").is_err());
+    }
+}
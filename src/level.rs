@@ -0,0 +1,130 @@
+use crate::{GameState, components::OnGameScreen, replay::Playback, resources::Score};
+use bevy::prelude::*;
+
+/// Highest level; the diplopod doesn't get any faster afterwards.
+pub const MAX_LEVEL: u8 = 10;
+/// Highest level that can be chosen as the starting speed in the menu.
+pub const MAX_STARTING_LEVEL: u8 = 5;
+/// Points that are needed to advance to the next level.
+pub const POINTS_PER_LEVEL: u16 = 15;
+/// Duration of a tick on the first level in seconds.
+const BASE_TIMESTEP: f64 = 0.075;
+/// Factor by which the duration of a tick shrinks on every level.
+const SPEEDUP: f64 = 0.92;
+
+const HUD_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+
+/// Speeds the game up whenever enough points were scored and shows the current level while
+/// playing.
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Game), init)
+            .add_systems(
+                Update,
+                (apply_timestep, update_hud)
+                    .run_if(in_state(GameState::Game))
+                    .run_if(resource_changed::<Level>),
+            )
+            .init_resource::<StartingLevel>()
+            .init_resource::<Level>();
+    }
+}
+
+/// Level a new game starts with, chosen in the menu.
+#[derive(PartialEq, Resource)]
+pub struct StartingLevel(pub u8);
+
+impl Default for StartingLevel {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+impl StartingLevel {
+    pub fn previous(&self) -> u8 {
+        if self.0 <= 1 {
+            MAX_STARTING_LEVEL
+        } else {
+            self.0 - 1
+        }
+    }
+
+    pub fn next(&self) -> u8 {
+        if self.0 >= MAX_STARTING_LEVEL {
+            1
+        } else {
+            self.0 + 1
+        }
+    }
+}
+
+/// Level of the current game and the level it started with.
+#[derive(Default, Resource)]
+pub struct Level {
+    pub starting: u8,
+    pub current: u8,
+}
+
+/// Returns the duration of a tick in seconds on the given level.
+pub fn timestep(level: u8) -> f64 {
+    BASE_TIMESTEP * SPEEDUP.powi(i32::from(level.max(1)) - 1)
+}
+
+#[derive(Component)]
+struct LevelText;
+
+/// Starts on the chosen level, or the level of the replay, and spawns the HUD that shows it.
+fn init(
+    mut commands: Commands,
+    starting_level: Res<StartingLevel>,
+    playback: Option<Res<Playback>>,
+    mut level: ResMut<Level>,
+) {
+    let starting = match playback {
+        Some(playback) => playback.replay.level,
+        None => starting_level.0,
+    };
+
+    *level = Level {
+        starting,
+        current: starting,
+    };
+
+    commands.spawn((
+        Text::new(format!("Level {}", level.current)),
+        TextFont {
+            font_size: 32.0,
+            ..default()
+        },
+        TextColor(HUD_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            right: Val::Px(10.0),
+            ..default()
+        },
+        LevelText,
+        OnGameScreen,
+    ));
+}
+
+/// Advances to the next level for every `POINTS_PER_LEVEL` points.
+pub fn update_level(score: Res<Score>, mut level: ResMut<Level>) {
+    let current = (u16::from(level.starting) + score.0 / POINTS_PER_LEVEL).min(MAX_LEVEL.into());
+
+    if u16::from(level.current) != current {
+        level.current = current as u8;
+    }
+}
+
+fn apply_timestep(level: Res<Level>, mut time: ResMut<Time<Fixed>>) {
+    time.set_timestep_seconds(timestep(level.current));
+}
+
+fn update_hud(level: Res<Level>, mut texts: Query<&mut Text, With<LevelText>>) {
+    for mut text in texts.iter_mut() {
+        text.0 = format!("Level {}", level.current);
+    }
+}
@@ -1,57 +1,96 @@
 mod camera;
+mod campaign;
 mod components;
+mod controls;
 mod diplopod;
+mod editor;
 mod events;
 mod food;
 mod gameover;
 mod graphics;
 mod highscore;
+mod level;
+mod maze;
 mod menu;
+mod name_entry;
 mod player_input;
+mod replay;
 mod resources;
+mod score;
+mod settings;
 mod setup;
+pub mod simulation;
+mod sound;
+mod touch;
 mod wall;
+mod winner;
 
 use bevy::prelude::*;
 use components::{GameState, OnGameScreen, Phase};
-use diplopod::{DiplopodSegments, Growth, eat, growth, movement};
+use diplopod::{Growth, Shrink, eat, growth, movement, shrink};
 use events::*;
-use food::{SpawnFood, spawn::spawn_food};
+use food::{FoodEaten, SpawnFood, spawn::spawn_food};
 use gameover::GameOver;
 use resources::*;
+use simulation::advance::advance;
 
-pub const TITLE: &str = "diplopod";
+pub use components::Position;
+pub use simulation::{GameSim, SimEvent};
 
-pub const CONSUMABLE_WIDTH: i32 = 47;
-pub const CONSUMABLE_HEIGHT: i32 = 26;
-pub const ARENA_WIDTH: i32 = CONSUMABLE_WIDTH + 1;
-pub const ARENA_HEIGHT: i32 = CONSUMABLE_HEIGHT + 1;
+pub const TITLE: &str = "diplopod";
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
+        let arena_sizes = ArenaSizes::from_args_or_env();
+
         app.add_plugins((
             highscore::HighscorePlugin,
             gameover::GameOverPlugin,
             menu::MenuPlugin,
+            name_entry::NameEntryPlugin,
             graphics::GraphicsPlugin,
             food::FoodPlugin,
             wall::WallPlugin,
             diplopod::DiplopodPlugin,
             camera::CameraPlugin,
+            simulation::SimulationPlugin,
+            replay::ReplayPlugin,
+            sound::SoundPlugin,
+            score::ScorePlugin,
+            level::LevelPlugin,
+            (
+                maze::MazePlugin,
+                campaign::CampaignPlugin,
+                editor::EditorPlugin,
+                winner::WinnerPlugin,
+                touch::TouchPlugin,
+                controls::ControlsPlugin,
+                settings::SettingsPlugin,
+            ),
         ))
         .add_systems(Startup, setup::setup)
         .add_systems(
             Update,
-            setup::set_default_font.run_if(resource_exists::<resources::DefaultFontHandle>),
+            (
+                setup::set_default_font.run_if(resource_exists::<resources::DefaultFontHandle>),
+                setup::resize.run_if(
+                    on_event::<bevy::window::WindowResized>.or(resource_changed::<ShownArena>),
+                ),
+                setup::update_cursor_visibility.run_if(state_changed::<GameState>),
+            ),
         )
         .add_systems(
             Update,
             (
                 (
-                    player_input::keyboard,
-                    player_input::gamepad,
+                    (
+                        player_input::keyboard,
+                        player_input::gamepad,
+                        player_input::swipe,
+                    )
+                        .run_if(not(resource_exists::<replay::Playback>)),
                     player_input::pause,
                 )
                     .in_set(Phase::Input)
@@ -66,11 +105,16 @@ impl Plugin for GamePlugin {
         .add_systems(
             FixedUpdate,
             ((
-                movement.after(Phase::Input).in_set(Phase::Movement),
+                advance.after(Phase::Input).in_set(Phase::Movement),
+                score::update_score,
+                level::update_level,
+                movement,
                 eat,
                 spawn_food.run_if(on_event::<SpawnFood>),
+                graphics::food_message,
                 graphics::show_message,
                 growth.run_if(on_event::<Growth>),
+                shrink.run_if(on_event::<Shrink>),
             )
                 .chain(),)
                 .run_if(in_state(GameState::Game))
@@ -79,12 +123,15 @@ impl Plugin for GamePlugin {
         .add_systems(OnExit(GameState::Game), despawn_screen::<OnGameScreen>)
         .init_state::<crate::GameState>()
         .insert_resource(ClearColor(Color::BLACK))
-        .insert_resource(DiplopodSegments::default())
-        .insert_resource(LastTailPosition::default())
-        .insert_resource(FreePositions::new(CONSUMABLE_WIDTH, CONSUMABLE_HEIGHT))
-        .insert_resource(Time::<Fixed>::from_seconds(0.075))
+        .insert_resource(Seed::from_args_or_env())
+        .insert_resource(arena_sizes.initial())
+        .insert_resource(ShownArena(arena_sizes.initial()))
+        .insert_resource(arena_sizes)
+        .insert_resource(Time::<Fixed>::from_seconds(level::timestep(1)))
         .add_event::<GameOver>()
         .add_event::<Growth>()
+        .add_event::<Shrink>()
+        .add_event::<FoodEaten>()
         .add_event::<SpawnFood>()
         .add_event::<ShowMessage>();
     }
@@ -7,6 +7,7 @@ fn main() {
                 primary_window: Some(Window {
                     title: snake::TITLE.into(),
                     resolution: (1280., 720.).into(),
+                    fit_canvas_to_parent: true,
                     ..default()
                 }),
                 ..default()
@@ -0,0 +1,628 @@
+use crate::{
+    TITLE,
+    components::Position,
+    food::AMOUNT_OF_POISON,
+    highscore::{ORGANIZATION, QUALIFIER},
+    resources::ArenaSize,
+};
+use anyhow::{Context, Result, anyhow};
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use directories::ProjectDirs;
+use std::{collections::VecDeque, fs::File, io::Write, path::PathBuf, str::FromStr};
+
+/// Loads mazes, arenas with interior walls, from `.maze` files and ships a set of built-in ones.
+pub struct MazePlugin;
+
+const MAZE_EXTENSION: &str = "maze";
+const BUILT_IN_MAZES: [&str; 3] = ["mazes/box.maze", "mazes/cross.maze", "mazes/corridors.maze"];
+const USER_MAZES: &str = "mazes";
+/// Prefix of the paths that refer to mazes saved with the editor instead of assets.
+const USER_MAZE_PREFIX: &str = "user:";
+
+impl Plugin for MazePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Maze>()
+            .register_asset_loader(MazeLoader)
+            .add_systems(Startup, load_mazes)
+            .init_resource::<SelectedMaze>()
+            .init_resource::<PlayedMaze>()
+            .init_resource::<UserMazes>();
+    }
+}
+
+/// An arena with walls at arbitrary positions, the start of the diplopod and the amount of food.
+///
+/// Mazes are stored as plain text. A few `key value` lines are followed by the tiles of the
+/// arena, one line per row from top to bottom:
+///
+/// ```text
+/// name Pillars
+/// direction right
+/// food 12
+/// ##########
+/// #........#
+/// #.S..##..#
+/// #.....FF.#
+/// ##########
+/// ```
+///
+/// `#` is a wall, `.` is a free tile, `F` is a free tile in a food zone and `S` is the start of
+/// the head. If there are food zones, food is only placed in them. The direction is one of `up`,
+/// `down`, `left`, `right` or `none`, in which case the diplopod waits for the first input. All
+/// keys are optional.
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct Maze {
+    pub name: String,
+    pub width: i32,
+    pub height: i32,
+    pub walls: Vec<Position>,
+    pub zones: Vec<Position>,
+    pub start: Position,
+    pub direction: IVec2,
+    pub food: u32,
+}
+
+impl Maze {
+    pub fn size(&self) -> ArenaSize {
+        ArenaSize::new(self.width, self.height)
+    }
+
+    pub fn contains(&self, pos: &Position) -> bool {
+        (0..self.width).contains(&pos.x) && (0..self.height).contains(&pos.y)
+    }
+
+    /// Returns all tiles that the head can reach from the start. With `wrap` the head leaves the
+    /// arena on one edge and enters it on the opposite one.
+    pub fn reachable(&self, wrap: bool) -> Vec<Position> {
+        let mut visited = vec![false; (self.width * self.height) as usize];
+        let index = |pos: &Position| (pos.y * self.width + pos.x) as usize;
+
+        for wall in &self.walls {
+            visited[index(wall)] = true;
+        }
+
+        let mut reachable = Vec::new();
+        let mut queue = VecDeque::from([self.start]);
+        visited[index(&self.start)] = true;
+
+        while let Some(pos) = queue.pop_front() {
+            reachable.push(pos);
+
+            for direction in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+                let mut next = Position {
+                    x: pos.x + direction.x,
+                    y: pos.y + direction.y,
+                };
+
+                if wrap {
+                    next.x = next.x.rem_euclid(self.width);
+                    next.y = next.y.rem_euclid(self.height);
+                }
+
+                if self.contains(&next) && !visited[index(&next)] {
+                    visited[index(&next)] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Returns the reachable tiles that food can be placed on.
+    pub fn food_positions(&self, wrap: bool) -> Vec<Position> {
+        let mut positions = self.reachable(wrap);
+        if !self.zones.is_empty() {
+            positions.retain(|pos| self.zones.contains(pos));
+        }
+
+        positions
+    }
+
+    /// Checks that the maze is playable.
+    pub fn validate(&self) -> Result<()> {
+        if !(ArenaSize::MIN.width..=ArenaSize::MAX.width).contains(&self.width)
+            || !(ArenaSize::MIN.height..=ArenaSize::MAX.height).contains(&self.height)
+        {
+            return Err(anyhow!(
+                "The maze is {} tiles large but must be between {} and {}",
+                self.size(),
+                ArenaSize::MIN,
+                ArenaSize::MAX
+            ));
+        }
+
+        let first = Position {
+            x: self.start.x + self.direction.x,
+            y: self.start.y + self.direction.y,
+        };
+        if self.direction != IVec2::ZERO && (!self.contains(&first) || self.walls.contains(&first))
+        {
+            return Err(anyhow!(
+                "The start direction leads the head straight into a wall"
+            ));
+        }
+
+        let food = self.food.checked_add(AMOUNT_OF_POISON).ok_or_else(|| {
+            anyhow!(
+                "The maze {} asks for {} food, which is too much",
+                self.name,
+                self.food
+            )
+        })?;
+        let free = self
+            .food_positions(false)
+            .iter()
+            .filter(|pos| **pos != self.start)
+            .count() as u32;
+        if free < food {
+            return Err(anyhow!(
+                "Only {} tiles for food are reachable from the start, but {} food and poison must be placed",
+                free,
+                food
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the maze in the same format it is read from.
+impl std::fmt::Display for Maze {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = match (self.direction.x, self.direction.y) {
+            (0, 1) => "up",
+            (0, -1) => "down",
+            (-1, 0) => "left",
+            (1, 0) => "right",
+            _ => "none",
+        };
+
+        writeln!(f, "name {}", self.name)?;
+        writeln!(f, "direction {}", direction)?;
+        writeln!(f, "food {}", self.food)?;
+
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let pos = Position { x, y };
+                let tile = if pos == self.start {
+                    'S'
+                } else if self.walls.contains(&pos) {
+                    '#'
+                } else if self.zones.contains(&pos) {
+                    'F'
+                } else {
+                    '.'
+                };
+                write!(f, "{}", tile)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Maze {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut name = String::from("Unnamed");
+        let mut direction = IVec2::ZERO;
+        let mut food = crate::food::AMOUNT_OF_FOOD;
+        let mut rows = Vec::new();
+
+        for (number, line) in s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim_end()))
+        {
+            if line.is_empty() {
+                continue;
+            }
+
+            if !rows.is_empty() || line.starts_with(['#', '.', 'F', 'S']) {
+                rows.push((number, line));
+                continue;
+            }
+
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            match key {
+                "name" => name = value.to_string(),
+                "direction" => {
+                    direction = match value {
+                        "up" => IVec2::Y,
+                        "down" => IVec2::NEG_Y,
+                        "left" => IVec2::NEG_X,
+                        "right" => IVec2::X,
+                        "none" => IVec2::ZERO,
+                        _ => return Err(anyhow!("Line {}: unknown direction {}", number, value)),
+                    }
+                }
+                "food" => {
+                    food = value
+                        .parse()
+                        .with_context(|| format!("Line {}: invalid food count {}", number, value))?
+                }
+                _ => return Err(anyhow!("Line {}: unknown key {}", number, key)),
+            }
+        }
+
+        let Some((_, first_row)) = rows.first() else {
+            return Err(anyhow!("The maze has no tiles"));
+        };
+        let width = first_row.chars().count() as i32;
+        let height = rows.len() as i32;
+
+        let mut walls = Vec::new();
+        let mut zones = Vec::new();
+        let mut start: Option<(usize, Position)> = None;
+
+        for (row, (number, line)) in rows.iter().enumerate() {
+            if line.chars().count() as i32 != width {
+                return Err(anyhow!(
+                    "Line {}: the row has {} tiles, but the first row has {}",
+                    number,
+                    line.chars().count(),
+                    width
+                ));
+            }
+
+            for (x, tile) in line.chars().enumerate() {
+                let pos = Position {
+                    x: x as i32,
+                    y: height - 1 - row as i32,
+                };
+
+                match tile {
+                    '#' => walls.push(pos),
+                    '.' => (),
+                    'F' => zones.push(pos),
+                    'S' => {
+                        if let Some((other, _)) = start {
+                            return Err(anyhow!(
+                                "Line {}: second start position, the first one is in line {}",
+                                number,
+                                other
+                            ));
+                        }
+                        start = Some((*number, pos));
+                    }
+                    _ => return Err(anyhow!("Line {}: unknown tile {:?}", number, tile)),
+                }
+            }
+        }
+
+        let Some((_, start)) = start else {
+            return Err(anyhow!("The maze has no start position S"));
+        };
+
+        let maze = Self {
+            name,
+            width,
+            height,
+            walls,
+            zones,
+            start,
+            direction,
+            food,
+        };
+        maze.validate()?;
+
+        Ok(maze)
+    }
+}
+
+#[derive(Default)]
+struct MazeLoader;
+
+impl AssetLoader for MazeLoader {
+    type Asset = Maze;
+    type Settings = ();
+    type Error = anyhow::Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Maze> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        std::str::from_utf8(&bytes)?
+            .parse()
+            .with_context(|| format!("Invalid maze {}", load_context.path().display()))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[MAZE_EXTENSION]
+    }
+}
+
+/// The mazes that can be chosen in the menu, the built-in ones followed by the ones saved with
+/// the editor.
+#[derive(Resource)]
+pub struct Mazes(pub Vec<Handle<Maze>>);
+
+impl Mazes {
+    /// The built-in mazes, which are also the levels of the campaign.
+    pub fn built_in(&self) -> &[Handle<Maze>] {
+        &self.0[..BUILT_IN_MAZES.len().min(self.0.len())]
+    }
+
+    /// Whether a built-in maze has the name, or a name that would be saved in the same file.
+    pub fn is_built_in_name(&self, name: &str, assets: &Assets<Maze>) -> bool {
+        let file = file_name(name);
+        self.built_in()
+            .iter()
+            .filter_map(|handle| assets.get(handle))
+            .any(|maze| file_name(&maze.name) == file)
+    }
+}
+
+/// The maze the next game is played in. Without a maze the arena is empty.
+#[derive(Default, Resource)]
+pub struct SelectedMaze(pub Option<Handle<Maze>>);
+
+/// The maze the running game is played in, if any. It differs from the [`SelectedMaze`] in the
+/// campaign, in the versus mode, while testing a maze of the editor and while playing a replay.
+#[derive(Default, Resource)]
+pub struct PlayedMaze(pub Option<Handle<Maze>>);
+
+/// The mazes saved with the editor and their file names in the data directory.
+#[derive(Default, Resource)]
+pub struct UserMazes(pub Vec<(String, Handle<Maze>)>);
+
+impl UserMazes {
+    /// Returns the path replays refer to the maze with. Mazes saved with the editor aren't assets,
+    /// so their file name is prefixed instead.
+    pub fn path(&self, maze: &Handle<Maze>) -> Option<String> {
+        maze.path().map(|path| path.to_string()).or_else(|| {
+            self.0
+                .iter()
+                .find(|(_, handle)| handle == maze)
+                .map(|(file, _)| format!("{}{}", USER_MAZE_PREFIX, file))
+        })
+    }
+
+    /// Returns the maze for a path created by [`UserMazes::path`].
+    pub fn load(&self, path: &str, asset_server: &AssetServer) -> Option<Handle<Maze>> {
+        match path.strip_prefix(USER_MAZE_PREFIX) {
+            Some(file) => self
+                .0
+                .iter()
+                .find(|(name, _)| name == file)
+                .map(|(_, handle)| handle.clone()),
+            None => Some(asset_server.load(path.to_string())),
+        }
+    }
+
+    /// Returns the maze that was saved with the name, or with another name that results in the
+    /// same file name.
+    pub fn saved(&self, name: &str) -> Option<&Handle<Maze>> {
+        let file = file_name(name);
+        self.0
+            .iter()
+            .find(|(saved, _)| *saved == file)
+            .map(|(_, handle)| handle)
+    }
+
+    /// Returns the first of the names `Custom 1`, `Custom 2` and so on that no saved maze uses.
+    pub fn unused_name(&self) -> String {
+        let mut number = 1;
+        loop {
+            let name = format!("Custom {}", number);
+            if self.saved(&name).is_none() {
+                return name;
+            }
+            number += 1;
+        }
+    }
+
+    /// Saves the maze in the data directory and adds it to the mazes, or replaces the maze that
+    /// was saved with the same name before.
+    pub fn save(
+        &mut self,
+        maze: Maze,
+        assets: &mut Assets<Maze>,
+        mazes: &mut Mazes,
+    ) -> Result<Handle<Maze>> {
+        let file = file_name(&maze.name);
+        write_maze_to_file(&maze, &file)?;
+
+        if let Some(handle) = self.saved(&maze.name) {
+            assets.insert(handle, maze);
+            return Ok(handle.clone());
+        }
+
+        let handle = assets.add(maze);
+        mazes.0.push(handle.clone());
+        self.0.push((file, handle.clone()));
+
+        Ok(handle)
+    }
+}
+
+/// Returns the name without a prefix that refers to a maze saved with the editor.
+pub fn display_path(path: &str) -> &str {
+    path.strip_prefix(USER_MAZE_PREFIX).unwrap_or(path)
+}
+
+/// File name of a maze saved with the editor, the lowercase name with dashes between the words.
+fn file_name(name: &str) -> String {
+    let stem = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_ascii_lowercase();
+
+    format!(
+        "{}.{}",
+        if stem.is_empty() { "maze" } else { &stem },
+        MAZE_EXTENSION
+    )
+}
+
+fn user_mazes_dir() -> Result<PathBuf> {
+    ProjectDirs::from(QUALIFIER, ORGANIZATION, TITLE)
+        .map(|projects_dirs| projects_dirs.data_dir().join(USER_MAZES))
+        .ok_or_else(|| {
+            anyhow!("Unable to retrieve path to data directory. Mazes can't be accessed.")
+        })
+}
+
+/// Writes the maze to the maze directory inside the platform specific data directory (e.g.
+/// `$HOME/.local/share/diplopod/mazes`). The file and all necessary directories are created by
+/// the function.
+fn write_maze_to_file(maze: &Maze, file: &str) -> Result<()> {
+    let mazes_dir = user_mazes_dir()?;
+    std::fs::create_dir_all(&mazes_dir)?;
+
+    let mut file = File::create(mazes_dir.join(file))?;
+    write!(file, "{}", maze)?;
+
+    Ok(())
+}
+
+/// Reads the mazes saved with the editor and their file names. Files that can't be parsed are
+/// skipped.
+fn read_mazes_from_files() -> Result<Vec<(String, Maze)>> {
+    let mut paths = std::fs::read_dir(user_mazes_dir()?)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == MAZE_EXTENSION))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+
+    Ok(paths
+        .iter()
+        .filter_map(|path| {
+            let file = path.file_name()?.to_string_lossy().into_owned();
+            match std::fs::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| content.parse())
+            {
+                Ok(maze) => Some((file, maze)),
+                Err(e) => {
+                    warn!("Skipping maze {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect())
+}
+
+/// Loads the built-in mazes and the ones saved with the editor.
+fn load_mazes(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut assets: ResMut<Assets<Maze>>,
+) {
+    let mut mazes: Vec<Handle<Maze>> = BUILT_IN_MAZES
+        .iter()
+        .map(|path| asset_server.load(*path))
+        .collect();
+    let mut user_mazes = UserMazes::default();
+
+    match read_mazes_from_files() {
+        Ok(files) => {
+            for (file, maze) in files {
+                let handle = assets.add(maze);
+                mazes.push(handle.clone());
+                user_mazes.0.push((file, handle));
+            }
+        }
+        // there are no saved mazes before the editor is used for the first time
+        Err(e) => debug!("{}", e),
+    }
+
+    commands.insert_resource(Mazes(mazes));
+    commands.insert_resource(user_mazes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL_MAZE: &str = "name Small
+direction right
+food 4
+############
+#..........#
+#..........#
+#....S.....#
+#..........#
+#..........#
+#..........#
+############
+";
+
+    #[test]
+    fn parses_a_valid_maze() {
+        let maze: Maze = SMALL_MAZE.parse().unwrap();
+
+        assert_eq!(maze.name, "Small");
+        assert_eq!(maze.size(), ArenaSize::new(12, 8));
+        assert_eq!(maze.start, Position { x: 5, y: 4 });
+        assert_eq!(maze.direction, IVec2::X);
+        assert_eq!(maze.food, 4);
+    }
+
+    #[test]
+    fn rejects_food_that_overflows() {
+        let content = SMALL_MAZE.replace("food 4", &format!("food {}", u32::MAX));
+
+        let error = content.parse::<Maze>().unwrap_err().to_string();
+
+        assert!(error.contains("Small"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_food_zone_that_cant_be_reached() {
+        let content = SMALL_MAZE
+            .replace(
+                "#..........#\n#..........#\n#..........#\n#####",
+                "#......###.#\n#......#F#.#\n#......###.#\n#####",
+            )
+            .replace("food 4", "food 1");
+
+        let error = content.parse::<Maze>().unwrap_err().to_string();
+
+        assert!(error.contains("Only 0 tiles"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_start_direction_into_a_wall() {
+        let content = SMALL_MAZE.replace("#....S.....#", "#....S#....#");
+
+        let error = content.parse::<Maze>().unwrap_err().to_string();
+
+        assert!(error.contains("straight into a wall"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_maze_without_or_with_two_starts() {
+        let without = SMALL_MAZE.replace('S', ".");
+        let twice = SMALL_MAZE.replace("#..........#\n#....S", "#.S........#\n#....S");
+
+        let error = without.parse::<Maze>().unwrap_err().to_string();
+        assert!(error.contains("no start position"), "{}", error);
+
+        let error = twice.parse::<Maze>().unwrap_err().to_string();
+        assert!(error.contains("second start position"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_an_unknown_direction() {
+        let content = SMALL_MAZE.replace("direction right", "direction sideways");
+
+        let error = content.parse::<Maze>().unwrap_err().to_string();
+
+        assert!(error.contains("unknown direction sideways"), "{}", error);
+    }
+}
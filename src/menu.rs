@@ -1,5 +1,15 @@
-use crate::{GameState, TITLE};
-use bevy::{app::AppExit, color::palettes::css::ANTIQUE_WHITE, prelude::*};
+use crate::{
+    GameState, TITLE,
+    controls::{Action, Controls},
+    level::StartingLevel,
+    maze::{Maze, Mazes, SelectedMaze},
+    resources::{ArenaSize, ArenaSizes},
+    settings::Config,
+    simulation::GameMode,
+};
+use bevy::{
+    app::AppExit, color::palettes::css::ANTIQUE_WHITE, ecs::system::SystemParam, prelude::*,
+};
 
 pub struct MenuPlugin;
 
@@ -8,7 +18,16 @@ impl Plugin for MenuPlugin {
         app.add_systems(OnEnter(crate::GameState::Menu), setup_menu)
             .add_systems(
                 Update,
-                (gamepad, keyboard).run_if(in_state(crate::GameState::Menu)),
+                (
+                    (gamepad, keyboard, pointer).run_if(in_state(crate::GameState::Menu)),
+                    update_arena_text.run_if(
+                        resource_changed::<ArenaSize>
+                            .or(resource_changed::<SelectedMaze>)
+                            .or(on_event::<AssetEvent<Maze>>),
+                    ),
+                    update_speed_text.run_if(resource_changed::<StartingLevel>),
+                    update_mode_text.run_if(resource_changed::<GameMode>),
+                ),
             )
             .add_systems(
                 OnExit(crate::GameState::Menu),
@@ -26,11 +45,29 @@ const BUTTON_SELECTED_BACKGROUND_COLOR: Color = Color::srgb(0.25, 0.25, 0.25);
 #[derive(Component)]
 struct OnMenuScreen;
 
-#[derive(Component, Default, Debug, PartialEq)]
+/// Text of the button that shows the chosen arena size.
+#[derive(Component)]
+struct ArenaText;
+
+/// Text of the button that shows the chosen starting speed.
+#[derive(Component)]
+struct SpeedText;
+
+/// Text of the button that shows the chosen game mode.
+#[derive(Component)]
+struct ModeText;
+
+#[derive(Component, Clone, Default, Debug, PartialEq)]
 pub enum MenuButton {
     #[default]
     Play,
+    Arena,
+    Speed,
+    Mode,
+    Editor,
+    Settings,
     Highscore,
+    Replays,
     Quit,
 }
 
@@ -38,15 +75,27 @@ impl MenuButton {
     fn previous(&self) -> Self {
         match *self {
             MenuButton::Play => MenuButton::Quit,
-            MenuButton::Highscore => MenuButton::Play,
-            MenuButton::Quit => MenuButton::Highscore,
+            MenuButton::Arena => MenuButton::Play,
+            MenuButton::Speed => MenuButton::Arena,
+            MenuButton::Mode => MenuButton::Speed,
+            MenuButton::Editor => MenuButton::Mode,
+            MenuButton::Settings => MenuButton::Editor,
+            MenuButton::Highscore => MenuButton::Settings,
+            MenuButton::Replays => MenuButton::Highscore,
+            MenuButton::Quit => MenuButton::Replays,
         }
     }
 
     fn next(&self) -> Self {
         match *self {
-            MenuButton::Play => MenuButton::Highscore,
-            MenuButton::Highscore => MenuButton::Quit,
+            MenuButton::Play => MenuButton::Arena,
+            MenuButton::Arena => MenuButton::Speed,
+            MenuButton::Speed => MenuButton::Mode,
+            MenuButton::Mode => MenuButton::Editor,
+            MenuButton::Editor => MenuButton::Settings,
+            MenuButton::Settings => MenuButton::Highscore,
+            MenuButton::Highscore => MenuButton::Replays,
+            MenuButton::Replays => MenuButton::Quit,
             MenuButton::Quit => MenuButton::Play,
         }
     }
@@ -55,64 +104,213 @@ impl MenuButton {
 #[derive(Default, Resource, Debug)]
 pub struct Selected(pub MenuButton);
 
+/// The options of the next game that can be changed in the menu. The speed and the arena size
+/// are changed in the config like on the settings screen, which applies and stores them.
+#[derive(SystemParam)]
+pub struct Options<'w> {
+    arena_sizes: Res<'w, ArenaSizes>,
+    arena_size: ResMut<'w, ArenaSize>,
+    mazes: Res<'w, Mazes>,
+    selected_maze: ResMut<'w, SelectedMaze>,
+    mode: ResMut<'w, GameMode>,
+    config: ResMut<'w, Config>,
+}
+
+impl Options<'_> {
+    /// Changes the option of the button to its previous value.
+    fn previous(&mut self, button: &MenuButton) {
+        match button {
+            MenuButton::Arena => self.previous_arena(),
+            MenuButton::Speed => self.config.speed = StartingLevel(self.config.speed).previous(),
+            MenuButton::Mode => *self.mode = self.mode.previous(),
+            _ => (),
+        }
+    }
+
+    /// Index of the selected maze in the mazes.
+    fn maze_index(&self) -> Option<usize> {
+        let selected = self.selected_maze.0.as_ref()?;
+        self.mazes.0.iter().position(|maze| maze == selected)
+    }
+
+    /// Plays in an arena of the size instead of a maze. The size is applied right away, because
+    /// the config doesn't change if it still contains the size while a custom arena from the
+    /// command line is chosen.
+    fn choose_arena_size(&mut self, size: ArenaSize) {
+        self.selected_maze.0 = None;
+        self.arena_size.set_if_neq(size);
+        if self.config.arena != size {
+            self.config.arena = size;
+        }
+    }
+
+    /// The arenas are cycled through in the order of their sizes followed by the mazes.
+    fn previous_arena(&mut self) {
+        let first_size = self.arena_sizes.0[0];
+
+        match self.maze_index() {
+            Some(0) => self.choose_arena_size(self.arena_sizes.previous(&first_size)),
+            Some(index) => self.selected_maze.0 = Some(self.mazes.0[index - 1].clone()),
+            None if *self.arena_size == first_size && !self.mazes.0.is_empty() => {
+                self.selected_maze.0 = self.mazes.0.last().cloned();
+            }
+            None => self.choose_arena_size(self.arena_sizes.previous(&self.arena_size)),
+        }
+    }
+
+    fn next_arena(&mut self) {
+        let last_size = self.arena_sizes.previous(&self.arena_sizes.0[0]);
+
+        match self.maze_index() {
+            Some(index) if index + 1 < self.mazes.0.len() => {
+                self.selected_maze.0 = Some(self.mazes.0[index + 1].clone());
+            }
+            Some(_) => self.choose_arena_size(self.arena_sizes.0[0]),
+            None if *self.arena_size == last_size && !self.mazes.0.is_empty() => {
+                self.selected_maze.0 = self.mazes.0.first().cloned();
+            }
+            None => self.choose_arena_size(self.arena_sizes.next(&self.arena_size)),
+        }
+    }
+
+    /// Changes the option of the button to its next value.
+    fn next(&mut self, button: &MenuButton) {
+        match button {
+            MenuButton::Arena => self.next_arena(),
+            MenuButton::Speed => self.config.speed = StartingLevel(self.config.speed).next(),
+            MenuButton::Mode => *self.mode = self.mode.next(),
+            _ => (),
+        }
+    }
+}
+
 fn keyboard(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    mut options: Options,
     mut selected: ResMut<Selected>,
     mut game_state: ResMut<NextState<GameState>>,
     mut app_exit_events: EventWriter<AppExit>,
     query: Query<(&mut BackgroundColor, &MenuButton)>,
 ) {
-    if keyboard_input.any_just_released([KeyCode::ArrowUp, KeyCode::KeyW, KeyCode::KeyK]) {
+    if controls.just_released(Action::Up, &keyboard_input) {
         selected.0 = selected.0.previous();
         update_selected_button(&selected.into(), query);
         return;
     }
 
-    if keyboard_input.any_just_released([KeyCode::ArrowDown, KeyCode::KeyS, KeyCode::KeyJ]) {
+    if controls.just_released(Action::Down, &keyboard_input) {
         selected.0 = selected.0.next();
         update_selected_button(&selected.into(), query);
         return;
     }
 
-    if keyboard_input.any_just_released([KeyCode::Enter, KeyCode::Space]) {
-        match &selected.0 {
-            MenuButton::Play => game_state.set(GameState::Game),
-            MenuButton::Highscore => game_state.set(GameState::Highscore),
-            MenuButton::Quit => {
-                app_exit_events.write(AppExit::Success);
-            }
-        }
+    if controls.just_released(Action::Left, &keyboard_input) {
+        options.previous(&selected.0);
+        return;
+    }
+
+    if controls.just_released(Action::Right, &keyboard_input) {
+        options.next(&selected.0);
+        return;
+    }
+
+    if controls.just_released(Action::Confirm, &keyboard_input) {
+        press(
+            &selected.0,
+            &mut options,
+            &mut game_state,
+            &mut app_exit_events,
+        );
     }
 }
 
 pub fn gamepad(
     gamepads: Query<&Gamepad>,
+    controls: Res<Controls>,
+    mut options: Options,
     mut selected: ResMut<Selected>,
     query: Query<(&mut BackgroundColor, &MenuButton)>,
     mut game_state: ResMut<NextState<GameState>>,
     mut app_exit_events: EventWriter<AppExit>,
 ) {
     for gamepad in gamepads.iter() {
-        if gamepad.just_released(GamepadButton::DPadUp) {
+        if controls.gamepad_just_released(Action::Up, gamepad) {
             selected.0 = selected.0.previous();
             update_selected_button(&selected.into(), query);
             return;
         }
 
-        if gamepad.just_released(GamepadButton::DPadDown) {
+        if controls.gamepad_just_released(Action::Down, gamepad) {
             selected.0 = selected.0.next();
             update_selected_button(&selected.into(), query);
             return;
         }
 
-        if gamepad.just_released(GamepadButton::South) {
-            match &selected.0 {
-                MenuButton::Play => game_state.set(GameState::Game),
-                MenuButton::Highscore => game_state.set(GameState::Highscore),
-                MenuButton::Quit => {
-                    app_exit_events.write(AppExit::Success);
-                }
-            }
+        if controls.gamepad_just_released(Action::Left, gamepad) {
+            options.previous(&selected.0);
+            return;
+        }
+
+        if controls.gamepad_just_released(Action::Right, gamepad) {
+            options.next(&selected.0);
+            return;
+        }
+
+        if controls.gamepad_just_released(Action::Confirm, gamepad) {
+            press(
+                &selected.0,
+                &mut options,
+                &mut game_state,
+                &mut app_exit_events,
+            );
+        }
+    }
+}
+
+/// Selects the button the mouse pointer hovers over and presses the button that was clicked or
+/// touched.
+fn pointer(
+    mut options: Options,
+    mut selected: ResMut<Selected>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut app_exit_events: EventWriter<AppExit>,
+    interactions: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    query: Query<(&mut BackgroundColor, &MenuButton)>,
+) {
+    let Some((interaction, button)) = interactions
+        .iter()
+        .find(|(interaction, _)| **interaction != Interaction::None)
+    else {
+        return;
+    };
+
+    if *interaction == Interaction::Pressed {
+        press(button, &mut options, &mut game_state, &mut app_exit_events);
+    }
+
+    if selected.0 != *button {
+        selected.0 = button.clone();
+        update_selected_button(&selected.into(), query);
+    }
+}
+
+/// Starts the game, changes the option or shows the screen of the button.
+fn press(
+    button: &MenuButton,
+    options: &mut Options,
+    game_state: &mut NextState<GameState>,
+    app_exit_events: &mut EventWriter<AppExit>,
+) {
+    match button {
+        MenuButton::Play => game_state.set(GameState::Game),
+        MenuButton::Arena | MenuButton::Speed | MenuButton::Mode => options.next(button),
+        MenuButton::Editor => game_state.set(GameState::Editor),
+        MenuButton::Settings => game_state.set(GameState::Settings),
+        MenuButton::Highscore => game_state.set(GameState::Highscore),
+        MenuButton::Replays => game_state.set(GameState::Replays),
+        MenuButton::Quit => {
+            app_exit_events.write(AppExit::Success);
         }
     }
 }
@@ -130,12 +328,79 @@ fn update_selected_button(
     }
 }
 
-fn setup_menu(mut commands: Commands, selected: Res<Selected>) {
+fn update_arena_text(
+    arena_size: Res<ArenaSize>,
+    selected_maze: Res<SelectedMaze>,
+    mazes: Res<Assets<Maze>>,
+    mut query: Query<&mut Text, With<ArenaText>>,
+) {
+    for mut text in query.iter_mut() {
+        text.0 = arena_text(&arena_size, &selected_maze, &mazes);
+    }
+}
+
+fn arena_text(
+    arena_size: &ArenaSize,
+    selected_maze: &SelectedMaze,
+    mazes: &Assets<Maze>,
+) -> String {
+    let name = match &selected_maze.0 {
+        Some(maze) => mazes
+            .get(maze)
+            .map_or_else(|| "...".to_string(), |maze| maze.name.clone()),
+        None => arena_size.name(),
+    };
+
+    format!("< Arena {} >", name)
+}
+
+fn update_speed_text(
+    starting_level: Res<StartingLevel>,
+    mut query: Query<&mut Text, With<SpeedText>>,
+) {
+    for mut text in query.iter_mut() {
+        text.0 = speed_text(&starting_level);
+    }
+}
+
+fn speed_text(starting_level: &StartingLevel) -> String {
+    format!("< Speed {} >", starting_level.0)
+}
+
+fn update_mode_text(mode: Res<GameMode>, mut query: Query<&mut Text, With<ModeText>>) {
+    for mut text in query.iter_mut() {
+        text.0 = mode_text(&mode);
+    }
+}
+
+fn mode_text(mode: &GameMode) -> String {
+    format!("< Mode {} >", mode)
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    selected: Res<Selected>,
+    arena_size: Res<ArenaSize>,
+    selected_maze: Res<SelectedMaze>,
+    mazes: Res<Assets<Maze>>,
+    starting_level: Res<StartingLevel>,
+    mode: Res<GameMode>,
+) {
+    // the title and all buttons fit into a window that is 720 pixels high
     let button_node = Node {
         width: Val::Px(340.0),
-        height: Val::Px(65.0),
-        margin: UiRect::all(Val::Px(20.0)),
-        padding: UiRect::all(Val::Px(45.0)),
+        height: Val::Px(50.0),
+        margin: UiRect::all(Val::Px(6.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    // options are smaller than the buttons that lead to another screen
+    let option_node = Node {
+        width: Val::Px(340.0),
+        height: Val::Px(34.0),
+        margin: UiRect::all(Val::Px(3.0)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
         ..default()
@@ -163,12 +428,12 @@ fn setup_menu(mut commands: Commands, selected: Res<Selected>) {
                     parent.spawn((
                         Text::new(TITLE),
                         TextFont {
-                            font_size: 128.0,
+                            font_size: 96.0,
                             ..default()
                         },
                         TextColor(TITLE_COLOR),
                         Node {
-                            margin: UiRect::all(Val::Px(50.0)),
+                            margin: UiRect::all(Val::Px(10.0)),
                             ..default()
                         },
                     ));
@@ -184,7 +449,100 @@ fn setup_menu(mut commands: Commands, selected: Res<Selected>) {
                             parent.spawn((
                                 Text::new("Play"),
                                 TextFont {
-                                    font_size: 64.0,
+                                    font_size: 44.0,
+                                    ..default()
+                                },
+                                TextColor(BUTTON_TEXT_COLOR),
+                            ));
+                        });
+
+                    parent
+                        .spawn((
+                            Button,
+                            option_node.clone(),
+                            background_color(&selected.0, &MenuButton::Arena),
+                            MenuButton::Arena,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new(arena_text(&arena_size, &selected_maze, &mazes)),
+                                TextFont {
+                                    font_size: 28.0,
+                                    ..default()
+                                },
+                                TextColor(BUTTON_TEXT_COLOR),
+                                ArenaText,
+                            ));
+                        });
+
+                    parent
+                        .spawn((
+                            Button,
+                            option_node.clone(),
+                            background_color(&selected.0, &MenuButton::Speed),
+                            MenuButton::Speed,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new(speed_text(&starting_level)),
+                                TextFont {
+                                    font_size: 28.0,
+                                    ..default()
+                                },
+                                TextColor(BUTTON_TEXT_COLOR),
+                                SpeedText,
+                            ));
+                        });
+
+                    parent
+                        .spawn((
+                            Button,
+                            option_node.clone(),
+                            background_color(&selected.0, &MenuButton::Mode),
+                            MenuButton::Mode,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new(mode_text(&mode)),
+                                TextFont {
+                                    font_size: 28.0,
+                                    ..default()
+                                },
+                                TextColor(BUTTON_TEXT_COLOR),
+                                ModeText,
+                            ));
+                        });
+
+                    parent
+                        .spawn((
+                            Button,
+                            button_node.clone(),
+                            background_color(&selected.0, &MenuButton::Editor),
+                            MenuButton::Editor,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new("Editor"),
+                                TextFont {
+                                    font_size: 44.0,
+                                    ..default()
+                                },
+                                TextColor(BUTTON_TEXT_COLOR),
+                            ));
+                        });
+
+                    parent
+                        .spawn((
+                            Button,
+                            button_node.clone(),
+                            background_color(&selected.0, &MenuButton::Settings),
+                            MenuButton::Settings,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new("Settings"),
+                                TextFont {
+                                    font_size: 44.0,
                                     ..default()
                                 },
                                 TextColor(BUTTON_TEXT_COLOR),
@@ -202,7 +560,25 @@ fn setup_menu(mut commands: Commands, selected: Res<Selected>) {
                             parent.spawn((
                                 Text::new("Highscore"),
                                 TextFont {
-                                    font_size: 64.0,
+                                    font_size: 44.0,
+                                    ..default()
+                                },
+                                TextColor(BUTTON_TEXT_COLOR),
+                            ));
+                        });
+
+                    parent
+                        .spawn((
+                            Button,
+                            button_node.clone(),
+                            background_color(&selected.0, &MenuButton::Replays),
+                            MenuButton::Replays,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new("Replays"),
+                                TextFont {
+                                    font_size: 44.0,
                                     ..default()
                                 },
                                 TextColor(BUTTON_TEXT_COLOR),
@@ -220,7 +596,7 @@ fn setup_menu(mut commands: Commands, selected: Res<Selected>) {
                             parent.spawn((
                                 Text::new("Quit"),
                                 TextFont {
-                                    font_size: 64.0,
+                                    font_size: 44.0,
                                     ..default()
                                 },
                                 TextColor(BUTTON_TEXT_COLOR),
@@ -0,0 +1,353 @@
+use crate::{
+    GameState,
+    highscore::InitialDelay,
+    resources::{Lastscore, Leaderboard},
+};
+use bevy::{
+    color::palettes::css::ANTIQUE_WHITE,
+    input::{
+        ButtonState,
+        keyboard::{Key, KeyboardInput},
+    },
+    prelude::*,
+};
+
+/// Adds a screen on which the player enters three initials for a new leaderboard entry.
+pub struct NameEntryPlugin;
+
+const TITLE_COLOR: Color = Color::Srgba(ANTIQUE_WHITE);
+const HEADLINE_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const INITIAL_COLOR: Color = Color::WHITE;
+const SELECTED_INITIAL_COLOR: Color = Color::srgb(1.0, 0.65, 0.0);
+
+const INITIALS: usize = 3;
+
+#[derive(Component)]
+struct OnNameEntryScreen;
+
+/// Text of the initial at the given index.
+#[derive(Component)]
+struct Initial(usize);
+
+/// Button that confirms the initials, for mice and touch screens.
+#[derive(Component)]
+struct ConfirmButton;
+
+/// The initials entered so far and the index of the initial that is currently changed.
+#[derive(Resource)]
+struct NameEntry {
+    initials: [char; INITIALS],
+    cursor: usize,
+}
+
+impl Default for NameEntry {
+    fn default() -> Self {
+        Self {
+            initials: ['A'; INITIALS],
+            cursor: 0,
+        }
+    }
+}
+
+impl NameEntry {
+    fn previous_letter(&mut self) {
+        let letter = &mut self.initials[self.cursor];
+        *letter = if *letter == 'A' {
+            'Z'
+        } else {
+            (*letter as u8 - 1) as char
+        };
+    }
+
+    fn next_letter(&mut self) {
+        let letter = &mut self.initials[self.cursor];
+        *letter = if *letter == 'Z' {
+            'A'
+        } else {
+            (*letter as u8 + 1) as char
+        };
+    }
+
+    fn previous_initial(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// Moves to the next initial and returns `false` if the last initial was already selected.
+    fn next_initial(&mut self) -> bool {
+        if self.cursor + 1 < INITIALS {
+            self.cursor += 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Plugin for NameEntryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::NameEntry), setup_name_entry)
+            .add_systems(
+                Update,
+                (
+                    (keyboard, gamepad, pointer)
+                        .run_if(in_state(GameState::NameEntry))
+                        .run_if(not(resource_exists::<InitialDelay>)),
+                    update_initials.run_if(resource_exists_and_changed::<NameEntry>),
+                ),
+            )
+            .add_systems(
+                OnExit(GameState::NameEntry),
+                (
+                    crate::despawn_screen::<OnNameEntryScreen>,
+                    remove_name_entry,
+                ),
+            );
+    }
+}
+
+/// Types letters directly, changes the selected letter with the up and down arrows and moves
+/// between the initials with the left and right arrows. Enter confirms the initials.
+fn keyboard(
+    mut keyboard_event: EventReader<KeyboardInput>,
+    mut name_entry: ResMut<NameEntry>,
+    leaderboard: ResMut<Leaderboard>,
+    game_state: ResMut<NextState<GameState>>,
+) {
+    let mut confirmed = false;
+
+    for ev in keyboard_event.read() {
+        if ev.state != ButtonState::Pressed {
+            continue;
+        }
+
+        match (&ev.logical_key, ev.key_code) {
+            (_, KeyCode::ArrowUp) => name_entry.next_letter(),
+            (_, KeyCode::ArrowDown) => name_entry.previous_letter(),
+            (_, KeyCode::ArrowLeft | KeyCode::Backspace) => name_entry.previous_initial(),
+            (_, KeyCode::ArrowRight) => {
+                name_entry.next_initial();
+            }
+            (_, KeyCode::Enter | KeyCode::NumpadEnter) => confirmed = true,
+            (Key::Character(text), _) => {
+                if let Some(letter) = text.chars().next().filter(char::is_ascii_alphabetic) {
+                    let cursor = name_entry.cursor;
+                    name_entry.initials[cursor] = letter.to_ascii_uppercase();
+                    name_entry.next_initial();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if confirmed {
+        confirm(&name_entry, leaderboard, game_state);
+    }
+}
+
+/// Changes the selected letter with up and down of the D-pad and moves between the initials with
+/// left and right. The A key moves to the next initial and confirms the last one.
+fn gamepad(
+    gamepads: Query<&Gamepad>,
+    mut name_entry: ResMut<NameEntry>,
+    leaderboard: ResMut<Leaderboard>,
+    game_state: ResMut<NextState<GameState>>,
+) {
+    for gamepad in gamepads.iter() {
+        if gamepad.just_pressed(GamepadButton::DPadUp) {
+            name_entry.next_letter();
+        }
+
+        if gamepad.just_pressed(GamepadButton::DPadDown) {
+            name_entry.previous_letter();
+        }
+
+        if gamepad.any_just_pressed([GamepadButton::DPadLeft, GamepadButton::East]) {
+            name_entry.previous_initial();
+        }
+
+        if gamepad.just_pressed(GamepadButton::DPadRight) {
+            name_entry.next_initial();
+        }
+
+        if gamepad.just_pressed(GamepadButton::South) && !name_entry.next_initial() {
+            confirm(&name_entry, leaderboard, game_state);
+            return;
+        }
+    }
+}
+
+/// Clicking or touching an initial selects it and doing so on the selected initial changes it to
+/// the next letter. The confirm button confirms the initials.
+fn pointer(
+    mut name_entry: ResMut<NameEntry>,
+    initials: Query<(&Interaction, &Initial), Changed<Interaction>>,
+    confirm_buttons: Query<&Interaction, (Changed<Interaction>, With<ConfirmButton>)>,
+    leaderboard: ResMut<Leaderboard>,
+    game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, initial) in initials.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if name_entry.cursor == initial.0 {
+            name_entry.next_letter();
+        } else {
+            name_entry.cursor = initial.0;
+        }
+    }
+
+    if confirm_buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        confirm(&name_entry, leaderboard, game_state);
+    }
+}
+
+/// Stores the initials in the latest leaderboard entry and shows the leaderboard.
+fn confirm(
+    name_entry: &NameEntry,
+    mut leaderboard: ResMut<Leaderboard>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if let Some(index) = leaderboard.latest {
+        leaderboard.entries[index].initials = name_entry.initials.iter().collect();
+    }
+
+    game_state.set(GameState::Highscore);
+}
+
+fn update_initials(
+    name_entry: Res<NameEntry>,
+    mut query: Query<(&mut Text, &mut TextColor, &Initial)>,
+) {
+    for (mut text, mut color, initial) in query.iter_mut() {
+        text.0 = name_entry.initials[initial.0].to_string();
+        color.0 = if initial.0 == name_entry.cursor {
+            SELECTED_INITIAL_COLOR
+        } else {
+            INITIAL_COLOR
+        };
+    }
+}
+
+fn remove_name_entry(mut commands: Commands) {
+    commands.remove_resource::<NameEntry>();
+}
+
+/// Creates the UI of the name entry screen. A new highscore is announced as such, any other
+/// entry with its rank in the leaderboard of the game mode.
+fn setup_name_entry(
+    mut commands: Commands,
+    lastscore: Res<Lastscore>,
+    leaderboard: Res<Leaderboard>,
+) {
+    commands.init_resource::<NameEntry>();
+
+    let title = match leaderboard.latest.and_then(|index| leaderboard.rank(index)) {
+        Some(1) | None => "New highscore!".to_string(),
+        Some(rank) => format!("You placed #{}", rank),
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            OnNameEntryScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(title),
+                        TextFont {
+                            font_size: 96.0,
+                            ..default()
+                        },
+                        TextColor(TITLE_COLOR),
+                        Node {
+                            margin: UiRect::all(Val::Px(30.0)),
+                            ..default()
+                        },
+                    ));
+
+                    parent.spawn((
+                        Text::new(format!("Score {}", lastscore.0)),
+                        TextFont {
+                            font_size: 48.0,
+                            ..default()
+                        },
+                        TextColor(HEADLINE_COLOR),
+                        Node {
+                            margin: UiRect::all(Val::Px(20.0)),
+                            ..default()
+                        },
+                    ));
+
+                    parent
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for index in 0..INITIALS {
+                                parent.spawn((
+                                    Text::new("A"),
+                                    TextFont {
+                                        font_size: 96.0,
+                                        ..default()
+                                    },
+                                    TextColor(if index == 0 {
+                                        SELECTED_INITIAL_COLOR
+                                    } else {
+                                        INITIAL_COLOR
+                                    }),
+                                    Node {
+                                        margin: UiRect::all(Val::Px(15.0)),
+                                        ..default()
+                                    },
+                                    Button,
+                                    Initial(index),
+                                ));
+                            }
+                        });
+
+                    parent.spawn((
+                        Text::new("Enter your initials"),
+                        TextFont {
+                            font_size: 32.0,
+                            ..default()
+                        },
+                        TextColor(HEADLINE_COLOR),
+                        Node {
+                            margin: UiRect::all(Val::Px(30.0)),
+                            ..default()
+                        },
+                    ));
+
+                    parent.spawn((
+                        Text::new("OK"),
+                        TextFont {
+                            font_size: 48.0,
+                            ..default()
+                        },
+                        TextColor(INITIAL_COLOR),
+                        Button,
+                        ConfirmButton,
+                    ));
+                });
+        });
+
+    commands.init_resource::<InitialDelay>();
+}
@@ -1,78 +1,167 @@
-use crate::{diplopod::DiplopodHead, resources::Paused};
-use bevy::prelude::*;
-
-pub fn keyboard(keyboard_input: Res<ButtonInput<KeyCode>>, mut heads: Query<&mut DiplopodHead>) {
-    let Some(mut head) = heads.iter_mut().next() else {
-        return;
-    };
-
-    let mut direction = Vec2::ZERO;
-
-    if keyboard_input.any_pressed([KeyCode::ArrowLeft, KeyCode::KeyA, KeyCode::KeyH]) {
-        direction = Vec2::new(-1.0, 0.0);
-    }
+use crate::{
+    controls::{Action, Controls, GamepadPlayers},
+    resources::Paused,
+    simulation::{GameMode, GameSim},
+    touch::TouchControls,
+};
+use bevy::{platform::collections::HashMap, prelude::*};
+
+/// Distance in logical pixels a finger has to be moved to steer the diplopod.
+const SWIPE_DISTANCE: f32 = 30.0;
+/// Distance in logical pixels a finger may move during a tap.
+const TAP_DISTANCE: f32 = 10.0;
+
+/// The keys of the directions of both players steer the diplopod, except in the versus mode,
+/// where each player uses their own directions, WASD and the arrows by default. Each key press
+/// queues one turn.
+pub fn keyboard(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    mut sim: ResMut<GameSim>,
+) {
+    for action in Action::DIRECTIONS
+        .into_iter()
+        .chain(Action::SECOND_DIRECTIONS)
+    {
+        if !keyboard_input.any_just_pressed(controls.keys(action).iter().copied()) {
+            continue;
+        }
 
-    if keyboard_input.any_pressed([KeyCode::ArrowRight, KeyCode::KeyD, KeyCode::KeyL]) {
-        direction = Vec2::new(1.0, 0.0);
+        let player =
+            if sim.mode() == GameMode::Versus && Action::SECOND_DIRECTIONS.contains(&action) {
+                1
+            } else {
+                0
+            };
+        sim.turn(player, action.direction());
     }
+}
 
-    if keyboard_input.any_pressed([KeyCode::ArrowUp, KeyCode::KeyW, KeyCode::KeyK]) {
-        direction = Vec2::new(direction.x, 1.0);
-    }
+/// Every gamepad steers the diplopod, except in the versus mode, where each player uses the
+/// gamepad that was assigned to them, see [`GamepadPlayers`]. Pressing a button bound to a
+/// direction of either player or tilting the left stick along an axis queues one turn, the stick
+/// has to be moved to another direction or released before it turns again.
+pub fn gamepad(
+    gamepads: Query<(Entity, &Gamepad)>,
+    controls: Res<Controls>,
+    gamepad_players: Res<GamepadPlayers>,
+    mut sim: ResMut<GameSim>,
+    mut tilts: Local<HashMap<Entity, IVec2>>,
+) {
+    for (entity, gamepad) in gamepads.iter() {
+        let mut directions: Vec<IVec2> = Action::DIRECTIONS
+            .iter()
+            .chain(&Action::SECOND_DIRECTIONS)
+            .filter(|action| controls.gamepad_just_pressed(**action, gamepad))
+            .map(Action::direction)
+            .collect();
+
+        let tilt = controls.stick_direction(gamepad);
+        if tilts.insert(entity, tilt) != Some(tilt) && tilt != IVec2::ZERO {
+            directions.push(tilt);
+        }
 
-    if keyboard_input.any_pressed([KeyCode::ArrowDown, KeyCode::KeyS, KeyCode::KeyJ]) {
-        direction = Vec2::new(direction.x, -1.0);
-    }
+        let players = if sim.mode() == GameMode::Versus {
+            match gamepad_players.player(entity) {
+                Some(player) if player < sim.players() => player..player + 1,
+                _ => continue,
+            }
+        } else {
+            0..sim.players()
+        };
 
-    if direction != Vec2::ZERO {
-        head.direction = direction;
+        for player in players {
+            for direction in &directions {
+                sim.turn(player, *direction);
+            }
+        }
     }
 }
 
-pub fn gamepad(gamepads: Query<&Gamepad>, mut heads: Query<&mut DiplopodHead>) {
-    const TILT: f32 = 0.9;
-
-    if let Some(mut head) = heads.iter_mut().next() {
-        let mut direction = Vec2::ZERO;
-
-        for gamepad in gamepads.iter() {
-            if let Some(left_stick_x) = gamepad.get(GamepadAxis::LeftStickX) {
-                if left_stick_x <= -TILT {
-                    direction = Vec2::new(-1.0, 0.0);
-                }
+/// Steers the diplopod of the first player in the direction a finger is swiped. The diplopod
+/// can be turned several times without lifting the finger.
+pub fn swipe(
+    touches: Res<Touches>,
+    mut anchors: Local<HashMap<u64, Vec2>>,
+    mut sim: ResMut<GameSim>,
+) {
+    for touch in touches.iter_just_pressed() {
+        anchors.insert(touch.id(), touch.position());
+    }
 
-                if left_stick_x >= TILT {
-                    direction = Vec2::new(1.0, 0.0);
-                }
-            }
+    for touch in touches
+        .iter_just_released()
+        .chain(touches.iter_just_canceled())
+    {
+        anchors.remove(&touch.id());
+    }
 
-            if let Some(left_stick_y) = gamepad.get(GamepadAxis::LeftStickY) {
-                if left_stick_y <= -TILT {
-                    direction = Vec2::new(direction.x, -1.0);
-                }
+    for touch in touches.iter() {
+        let Some(anchor) = anchors.get_mut(&touch.id()) else {
+            continue;
+        };
 
-                if left_stick_y >= TILT {
-                    direction = Vec2::new(direction.x, 1.0);
-                }
-            }
+        let delta = touch.position() - *anchor;
+        if delta.length() < SWIPE_DISTANCE {
+            continue;
         }
 
-        if direction != Vec2::ZERO {
-            head.direction = direction;
-        }
+        // the y axis of the window points down, the one of the arena up
+        let direction = if delta.x.abs() > delta.y.abs() {
+            IVec2::new(delta.x.signum() as i32, 0)
+        } else {
+            IVec2::new(0, -delta.y.signum() as i32)
+        };
+        sim.turn(0, direction);
+        *anchor = touch.position();
     }
 }
 
-/// Pause game and all sounds when `Space` or `p` is pressed.
-pub fn pause(keyboard_input: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
-    if keyboard_input.any_just_released([KeyCode::Space, KeyCode::KeyP]) {
+/// Whether a finger was lifted without being swiped. Taps are handled by the buttons of the
+/// on-screen D-pad while it is shown.
+fn tapped(touches: &Touches, touch_controls: &TouchControls) -> bool {
+    !touch_controls.dpad
+        && touches
+            .iter_just_released()
+            .any(|touch| touch.distance().length() < TAP_DISTANCE)
+}
+
+/// Whether a key or gamepad button bound to pause was released.
+fn pause_released(
+    controls: &Controls,
+    keyboard_input: &ButtonInput<KeyCode>,
+    gamepads: &Query<&Gamepad>,
+) -> bool {
+    controls.just_released(Action::Pause, keyboard_input)
+        || gamepads
+            .iter()
+            .any(|gamepad| controls.gamepad_just_released(Action::Pause, gamepad))
+}
+
+/// Pause game and all sounds when pause is pressed or the screen is tapped.
+pub fn pause(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    controls: Res<Controls>,
+    touches: Res<Touches>,
+    touch_controls: Res<TouchControls>,
+    mut commands: Commands,
+) {
+    if pause_released(&controls, &keyboard_input, &gamepads) || tapped(&touches, &touch_controls) {
         commands.init_resource::<Paused>();
     }
 }
 
-/// Continue game and all sounds when `Space` or `p` is pressed.
-pub fn unpause(keyboard_input: Res<ButtonInput<KeyCode>>, mut commands: Commands) {
-    if keyboard_input.any_just_released([KeyCode::Space, KeyCode::KeyP]) {
+/// Continue game and all sounds when pause is pressed or the screen is tapped.
+pub fn unpause(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    controls: Res<Controls>,
+    touches: Res<Touches>,
+    touch_controls: Res<TouchControls>,
+    mut commands: Commands,
+) {
+    if pause_released(&controls, &keyboard_input, &gamepads) || tapped(&touches, &touch_controls) {
         commands.remove_resource::<Paused>();
     }
 }
@@ -0,0 +1,701 @@
+use crate::{
+    GameState, TITLE,
+    controls::{Action, Controls},
+    highscore::{ORGANIZATION, QUALIFIER},
+    level::MAX_LEVEL,
+    maze::{self, Maze, UserMazes},
+    resources::ArenaSize,
+    simulation::GameMode,
+};
+use anyhow::{Context, Result, anyhow};
+use bevy::{color::palettes::css::ANTIQUE_WHITE, prelude::*};
+use directories::ProjectDirs;
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Records the inputs of every game and adds a screen that lists the saved replays and plays
+/// them back.
+pub struct ReplayPlugin;
+
+const TITLE_COLOR: Color = Color::Srgba(ANTIQUE_WHITE);
+const HEADLINE_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const BUTTON_TEXT_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const BUTTON_BACKGROUND_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+const BUTTON_SELECTED_BACKGROUND_COLOR: Color = Color::srgb(0.25, 0.25, 0.25);
+
+const REPLAYS: &str = "replays";
+const REPLAY_EXTENSION: &str = "replay";
+/// Number of replays that are listed on the replay screen.
+const MAX_LISTED_REPLAYS: usize = 8;
+/// Number of replays that are kept, older ones are deleted.
+const MAX_REPLAYS: usize = 100;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Game), start_recording)
+            .add_systems(OnEnter(GameState::Replays), setup_replays)
+            .add_systems(
+                Update,
+                (gamepad, keyboard, pointer).run_if(in_state(GameState::Replays)),
+            )
+            .add_systems(
+                OnExit(GameState::Replays),
+                crate::despawn_screen::<OnReplaysScreen>,
+            )
+            .init_resource::<Recording>()
+            .init_resource::<SelectedReplay>();
+    }
+}
+
+#[derive(Component)]
+struct OnReplaysScreen;
+
+/// A direction change that was applied to the head before the given tick was simulated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayInput {
+    pub tick: u64,
+    pub direction: IVec2,
+}
+
+/// Everything that is needed to simulate a game again: the seed of the random number generator,
+/// the size of the arena, the maze, the starting level, the game mode and the direction changes
+/// of the player.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub score: u16,
+    pub arena: ArenaSize,
+    pub level: u8,
+    pub mode: GameMode,
+    /// Path of the maze the game was played in, if any. See [`UserMazes::path`].
+    pub maze: Option<String>,
+    /// Index of the level a campaign was started with.
+    pub campaign: usize,
+    pub inputs: Vec<ReplayInput>,
+}
+
+impl Replay {
+    /// Name of the maze file or of the arena size the game was played in.
+    pub fn arena_name(&self) -> String {
+        match &self.maze {
+            Some(maze) => std::path::Path::new(maze::display_path(maze))
+                .file_stem()
+                .map_or_else(|| maze.clone(), |stem| stem.to_string_lossy().into_owned()),
+            None => self.arena.name(),
+        }
+    }
+
+    /// Returns the direction that was applied before the given tick or zero if the direction
+    /// didn't change.
+    pub fn direction_at(&self, tick: u64) -> IVec2 {
+        self.inputs
+            .binary_search_by_key(&tick, |input| input.tick)
+            .map_or(IVec2::ZERO, |index| self.inputs[index].direction)
+    }
+}
+
+/// Writes the replay as plain text: a `seed`, a `score`, an `arena`, a `level`, a `mode`, a `maze`
+/// and a `campaign` line followed by one line per input with the tick and the direction. The
+/// `maze` line is empty if the game wasn't played in a maze.
+impl std::fmt::Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "score {}", self.score)?;
+        writeln!(f, "arena {}", self.arena)?;
+        writeln!(f, "level {}", self.level)?;
+        writeln!(f, "mode {}", self.mode)?;
+        match &self.maze {
+            Some(maze) => writeln!(f, "maze {}", maze)?,
+            None => writeln!(f, "maze")?,
+        }
+        writeln!(f, "campaign {}", self.campaign)?;
+
+        for input in &self.inputs {
+            writeln!(
+                f,
+                "{} {} {}",
+                input.tick, input.direction.x, input.direction.y
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut lines = s.lines();
+        let mut header = |name: &str| -> Result<String> {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(name))
+                .map(|value| value.trim().to_string())
+                .ok_or_else(|| anyhow!("Replay is missing the {} line", name))
+        };
+
+        let seed = header("seed")?.parse()?;
+        let score = header("score")?.parse()?;
+        let arena = header("arena")?.parse()?;
+        let level: u8 = header("level")?.parse()?;
+        let mode = header("mode")?.parse()?;
+        let maze = Some(header("maze")?).filter(|maze| !maze.is_empty());
+        let campaign = header("campaign")?.parse()?;
+        if !(1..=MAX_LEVEL).contains(&level) {
+            return Err(anyhow!("Invalid replay level {}", level));
+        }
+
+        let mut inputs: Vec<ReplayInput> = Vec::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let values = line
+                .split_whitespace()
+                .map(|value| value.parse::<i64>())
+                .collect::<Result<Vec<_>, _>>()
+                .with_context(|| format!("Invalid replay input {}", line))?;
+
+            let [tick, x, y] = values[..] else {
+                return Err(anyhow!("Invalid replay input {}", line));
+            };
+
+            let input = ReplayInput {
+                tick: tick.try_into()?,
+                direction: IVec2::new(x.try_into()?, y.try_into()?),
+            };
+
+            if inputs.last().is_some_and(|last| last.tick >= input.tick) {
+                return Err(anyhow!("Replay inputs are not ordered by tick"));
+            }
+            inputs.push(input);
+        }
+
+        Ok(Self {
+            seed,
+            score,
+            arena,
+            level,
+            mode,
+            maze,
+            campaign,
+            inputs,
+        })
+    }
+}
+
+/// The inputs of the game that is currently played.
+#[derive(Default, Resource)]
+pub struct Recording(pub Vec<ReplayInput>);
+
+/// The replay that is currently played back and the maze it is played in. While it exists, the
+/// inputs of the player are ignored and the game is started with the options of the replay
+/// instead of the ones chosen in the menu.
+#[derive(Resource)]
+pub struct Playback {
+    pub replay: Replay,
+    pub maze: Option<Handle<Maze>>,
+}
+
+/// The replays listed on the replay screen and the index of the selected one.
+#[derive(Default, Resource)]
+struct SelectedReplay {
+    replays: Vec<Replay>,
+    index: usize,
+}
+
+#[derive(Component)]
+struct ReplayButton(usize);
+
+/// Button that returns to the menu, for mice and touch screens.
+#[derive(Component)]
+struct BackButton;
+
+fn start_recording(mut recording: ResMut<Recording>) {
+    recording.0.clear();
+}
+
+fn replays_dir() -> Result<PathBuf> {
+    ProjectDirs::from(QUALIFIER, ORGANIZATION, TITLE)
+        .map(|projects_dirs| projects_dirs.data_dir().join(REPLAYS))
+        .ok_or_else(|| {
+            anyhow!("Unable to retrieve path to data directory. Replays can't be accessed.")
+        })
+}
+
+/// Writes the replay to the replay directory inside the platform specific data directory (e.g.
+/// `$HOME/.local/share/diplopod/replays`). The file is named after the milliseconds since the Unix
+/// epoch, followed by a counter if another replay was saved in the same millisecond. The file and
+/// all necessary directories are created by the function. Only the most recent `MAX_REPLAYS`
+/// replays are kept.
+pub fn write_replay_to_file(replay: &Replay) -> Result<()> {
+    let replays_dir = replays_dir()?;
+    std::fs::create_dir_all(&replays_dir)?;
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let mut counter = 0;
+    let mut file = loop {
+        let name = if counter == 0 {
+            timestamp.to_string()
+        } else {
+            format!("{}-{}", timestamp, counter)
+        };
+
+        match File::create_new(replays_dir.join(name).with_extension(REPLAY_EXTENSION)) {
+            Ok(file) => break file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => counter += 1,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    write!(file, "{}", replay)?;
+
+    let paths = replay_paths(&replays_dir)?;
+    for path in paths.iter().take(paths.len().saturating_sub(MAX_REPLAYS)) {
+        std::fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the paths of the replays in the directory from the oldest to the most recent one.
+fn replay_paths(replays_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = std::fs::read_dir(replays_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == REPLAY_EXTENSION))
+        .collect::<Vec<PathBuf>>();
+
+    paths.sort_by_cached_key(|path| {
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let (timestamp, counter) = stem.split_once('-').unwrap_or((stem, "0"));
+        (
+            timestamp.parse::<u128>().unwrap_or_default(),
+            counter.parse::<u32>().unwrap_or_default(),
+            path.clone(),
+        )
+    });
+
+    Ok(paths)
+}
+
+/// Reads the most recent replays from the replay directory. Files that can't be parsed are
+/// skipped.
+fn read_replays_from_files() -> Result<Vec<Replay>> {
+    let paths = replay_paths(&replays_dir()?)?;
+
+    Ok(paths
+        .iter()
+        .rev()
+        .filter_map(|path| {
+            match std::fs::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| content.parse::<Replay>())
+            {
+                Ok(replay) => Some(replay),
+                Err(e) => {
+                    warn!("Skipping replay {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .take(MAX_LISTED_REPLAYS)
+        .collect())
+}
+
+fn keyboard(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    user_mazes: Res<UserMazes>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    mut selected: ResMut<SelectedReplay>,
+    mut game_state: ResMut<NextState<GameState>>,
+    query: Query<(&mut BackgroundColor, &ReplayButton)>,
+) {
+    if controls.just_released(Action::Back, &keyboard_input) {
+        game_state.set(GameState::Menu);
+        return;
+    }
+
+    if selected.replays.is_empty() {
+        return;
+    }
+
+    if controls.just_released(Action::Up, &keyboard_input) {
+        selected.index = (selected.index + selected.replays.len() - 1) % selected.replays.len();
+        update_selected_button(&selected, query);
+        return;
+    }
+
+    if controls.just_released(Action::Down, &keyboard_input) {
+        selected.index = (selected.index + 1) % selected.replays.len();
+        update_selected_button(&selected, query);
+        return;
+    }
+
+    if controls.just_released(Action::Confirm, &keyboard_input) {
+        play(
+            &mut commands,
+            &asset_server,
+            &user_mazes,
+            &selected.replays[selected.index],
+        );
+        game_state.set(GameState::Game);
+    }
+}
+
+fn gamepad(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    user_mazes: Res<UserMazes>,
+    gamepads: Query<&Gamepad>,
+    controls: Res<Controls>,
+    mut selected: ResMut<SelectedReplay>,
+    mut game_state: ResMut<NextState<GameState>>,
+    query: Query<(&mut BackgroundColor, &ReplayButton)>,
+) {
+    for gamepad in gamepads.iter() {
+        if controls.gamepad_just_released(Action::Back, gamepad) {
+            game_state.set(GameState::Menu);
+            return;
+        }
+
+        if selected.replays.is_empty() {
+            return;
+        }
+
+        if controls.gamepad_just_released(Action::Up, gamepad) {
+            selected.index = (selected.index + selected.replays.len() - 1) % selected.replays.len();
+            update_selected_button(&selected, query);
+            return;
+        }
+
+        if controls.gamepad_just_released(Action::Down, gamepad) {
+            selected.index = (selected.index + 1) % selected.replays.len();
+            update_selected_button(&selected, query);
+            return;
+        }
+
+        if controls.gamepad_just_released(Action::Confirm, gamepad) {
+            play(
+                &mut commands,
+                &asset_server,
+                &user_mazes,
+                &selected.replays[selected.index],
+            );
+            game_state.set(GameState::Game);
+        }
+    }
+}
+
+/// Selects the replay the mouse pointer hovers over and plays the replay that was clicked or
+/// touched. The back button returns to the menu.
+fn pointer(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    user_mazes: Res<UserMazes>,
+    mut selected: ResMut<SelectedReplay>,
+    mut game_state: ResMut<NextState<GameState>>,
+    replay_buttons: Query<(&Interaction, &ReplayButton), Changed<Interaction>>,
+    back_buttons: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+    query: Query<(&mut BackgroundColor, &ReplayButton)>,
+) {
+    if back_buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        game_state.set(GameState::Menu);
+        return;
+    }
+
+    for (interaction, button) in replay_buttons.iter() {
+        if *interaction == Interaction::Hovered && selected.index != button.0 {
+            selected.index = button.0;
+            update_selected_button(&selected, query);
+            return;
+        }
+
+        if *interaction == Interaction::Pressed {
+            selected.index = button.0;
+            play(
+                &mut commands,
+                &asset_server,
+                &user_mazes,
+                &selected.replays[selected.index],
+            );
+            game_state.set(GameState::Game);
+        }
+    }
+}
+
+/// Plays the replay back in the arena, the maze and the game mode it was recorded in.
+fn play(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    user_mazes: &UserMazes,
+    replay: &Replay,
+) {
+    let maze = replay.maze.as_ref().and_then(|maze| {
+        let handle = user_mazes.load(maze, asset_server);
+        if handle.is_none() {
+            warn!("Maze {} of the replay doesn't exist anymore", maze);
+        }
+        handle
+    });
+    commands.insert_resource(Playback {
+        replay: replay.clone(),
+        maze,
+    });
+}
+
+fn update_selected_button(
+    selected: &SelectedReplay,
+    mut query: Query<(&mut BackgroundColor, &ReplayButton)>,
+) {
+    for (mut background_color, button) in &mut query {
+        if selected.index == button.0 {
+            background_color.0 = BUTTON_SELECTED_BACKGROUND_COLOR;
+        } else {
+            background_color.0 = BUTTON_BACKGROUND_COLOR;
+        }
+    }
+}
+
+/// Creates the UI of the replay screen.
+fn setup_replays(mut commands: Commands, mut selected: ResMut<SelectedReplay>) {
+    selected.index = 0;
+    selected.replays = read_replays_from_files().unwrap_or_else(|e| {
+        warn!("{}", e);
+        Vec::new()
+    });
+
+    let button_node = Node {
+        width: Val::Px(480.0),
+        height: Val::Px(45.0),
+        margin: UiRect::all(Val::Px(5.0)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            OnReplaysScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new(TITLE),
+                        TextFont {
+                            font_size: 128.0,
+                            ..default()
+                        },
+                        TextColor(TITLE_COLOR),
+                        Node {
+                            margin: UiRect::all(Val::Px(50.0)),
+                            ..default()
+                        },
+                    ));
+
+                    parent.spawn((
+                        Text::new("Replays"),
+                        TextFont {
+                            font_size: 64.0,
+                            ..default()
+                        },
+                        TextColor(HEADLINE_COLOR),
+                        Node {
+                            margin: UiRect::all(Val::Px(25.0)),
+                            ..default()
+                        },
+                    ));
+
+                    if selected.replays.is_empty() {
+                        parent.spawn((
+                            Text::new("No replays yet"),
+                            TextFont {
+                                font_size: 32.0,
+                                ..default()
+                            },
+                            TextColor(BUTTON_TEXT_COLOR),
+                        ));
+                    }
+
+                    for (index, replay) in selected.replays.iter().enumerate() {
+                        parent
+                            .spawn((
+                                Button,
+                                button_node.clone(),
+                                background_color(selected.index, index),
+                                ReplayButton(index),
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Text::new(format!(
+                                        "Score {} - {} {} - Seed {}",
+                                        replay.score,
+                                        replay.mode,
+                                        replay.arena_name(),
+                                        replay.seed
+                                    )),
+                                    TextFont {
+                                        font_size: 32.0,
+                                        ..default()
+                                    },
+                                    TextColor(BUTTON_TEXT_COLOR),
+                                ));
+                            });
+                    }
+
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                margin: UiRect::all(Val::Px(25.0)),
+                                ..default()
+                            },
+                            BackButton,
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                Text::new("Back"),
+                                TextFont {
+                                    font_size: 32.0,
+                                    ..default()
+                                },
+                                TextColor(HEADLINE_COLOR),
+                            ));
+                        });
+                });
+        });
+
+    fn background_color(selected: usize, button: usize) -> BackgroundColor {
+        if selected == button {
+            return BUTTON_SELECTED_BACKGROUND_COLOR.into();
+        }
+
+        BUTTON_BACKGROUND_COLOR.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{FoodKind, GameSim};
+
+    fn replay(maze: Option<&str>) -> Replay {
+        Replay {
+            seed: 42,
+            score: 123,
+            arena: ArenaSize::new(60, 34),
+            level: 3,
+            mode: GameMode::Wrap,
+            maze: maze.map(str::to_string),
+            campaign: 2,
+            inputs: vec![
+                ReplayInput {
+                    tick: 0,
+                    direction: IVec2::X,
+                },
+                ReplayInput {
+                    tick: 17,
+                    direction: IVec2::NEG_Y,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn a_written_replay_is_read_again() {
+        for replay in [replay(None), replay(Some("mazes/spiral.maze"))] {
+            assert_eq!(replay.to_string().parse::<Replay>().unwrap(), replay);
+        }
+    }
+
+    #[test]
+    fn a_replay_without_all_headers_is_rejected() {
+        let text = replay(None).to_string();
+        for header in ["arena", "level", "mode", "maze", "campaign"] {
+            let text = text
+                .lines()
+                .filter(|line| !line.starts_with(header))
+                .collect::<Vec<_>>()
+                .join("\n");
+            assert!(text.parse::<Replay>().is_err(), "{}", header);
+        }
+    }
+
+    #[test]
+    fn the_recorded_inputs_result_in_the_same_game() {
+        let seed = 7;
+        let mut sim = GameSim::new(32, 18, GameMode::Classic, seed);
+        let mut inputs = Vec::new();
+
+        // heads for the closest food like a player would and records the direction changes
+        // like the game does
+        while !sim.is_game_over() && sim.tick() < 2000 {
+            let head = sim.head();
+            let target = sim
+                .food()
+                .iter()
+                .filter(|(_, kind)| *kind != FoodKind::Poison)
+                .map(|(pos, _)| *pos)
+                .min_by_key(|pos| (pos.x - head.x).abs() + (pos.y - head.y).abs())
+                .unwrap_or(head);
+            let direction = if target.x != head.x {
+                IVec2::new((target.x - head.x).signum(), 0)
+            } else {
+                IVec2::new(0, (target.y - head.y).signum())
+            };
+
+            let tick = sim.tick();
+            let previous_direction = sim.direction();
+            sim.step(direction);
+            if sim.direction() != previous_direction {
+                inputs.push(ReplayInput {
+                    tick,
+                    direction: sim.direction(),
+                });
+            }
+        }
+        assert!(sim.score() > 0);
+
+        let replay = Replay {
+            seed,
+            score: sim.score(),
+            arena: ArenaSize::new(32, 18),
+            level: 1,
+            mode: GameMode::Classic,
+            maze: None,
+            campaign: 0,
+            inputs,
+        };
+        let mut played = GameSim::new(32, 18, GameMode::Classic, replay.seed);
+        while !played.is_game_over() && played.tick() < sim.tick() {
+            played.step(replay.direction_at(played.tick()));
+        }
+
+        assert_eq!(played.score(), sim.score());
+        assert_eq!(played.segments(), sim.segments());
+        assert_eq!(played.tick(), sim.tick());
+        assert_eq!(played.is_game_over(), sim.is_game_over());
+    }
+}
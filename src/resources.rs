@@ -1,72 +1,366 @@
+use crate::simulation::GameMode;
+use anyhow::{Result, anyhow};
 use bevy::prelude::*;
-use rand::{rng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use std::{str::FromStr, time::Duration};
 
-use crate::components::Position;
+#[derive(Default, Debug, PartialEq, Resource)]
+pub struct TileSize(pub i32);
+
+#[derive(Resource)]
+pub struct DefaultFontHandle(pub Handle<Font>);
+
+/// Score of the current game.
+#[derive(Default, Resource)]
+pub struct Score(pub u16);
+
+/// Number of entries that are kept in the leaderboard of each game mode.
+pub const LEADERBOARD_SIZE: usize = 10;
 
-#[derive(Clone, Resource, Default)]
-pub struct FreePositions {
-    pub positions: Vec<Position>,
-    width: i32,
-    height: i32,
+/// A finished game in the leaderboard.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub initials: String,
+    pub score: u16,
+    /// Number of segments of the diplopod at the end of the game.
+    pub length: u16,
+    /// Played time in seconds.
+    pub duration: u64,
+    pub seed: u64,
+    /// Seconds since the Unix epoch when the game ended.
+    pub timestamp: u64,
+    pub mode: GameMode,
 }
 
-impl FreePositions {
-    pub fn new(width: i32, height: i32) -> Self {
-        let positions = Self::new_positions(width, height);
-        Self {
-            positions,
-            width,
-            height,
+/// The best games of all game modes ordered by score, highest first.
+#[derive(Default, Debug, Resource, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+    /// Index of the entry of the last game, if it made it into the leaderboard.
+    #[serde(skip)]
+    pub latest: Option<usize>,
+}
+
+impl Leaderboard {
+    pub fn highscore(&self, mode: GameMode) -> u16 {
+        self.entries(mode)
+            .next()
+            .map_or(0, |(_, entry)| entry.score)
+    }
+
+    /// The entries of the game mode together with their index in all entries.
+    pub fn entries(&self, mode: GameMode) -> impl Iterator<Item = (usize, &LeaderboardEntry)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(move |(_, entry)| entry.mode == mode)
+    }
+
+    /// Rank of the entry at the index within the leaderboard of its game mode, starting at one.
+    pub fn rank(&self, index: usize) -> Option<usize> {
+        let mode = self.entries.get(index)?.mode;
+        self.entries(mode)
+            .position(|(other, _)| other == index)
+            .map(|position| position + 1)
+    }
+
+    /// Inserts the entry behind all entries with the same or a higher score and drops the entry
+    /// that no longer fits into the leaderboard of its game mode.
+    pub fn insert(&mut self, entry: LeaderboardEntry) {
+        let mode = entry.mode;
+        let index = self
+            .entries
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(self.entries.len());
+
+        self.entries.insert(index, entry);
+        self.latest = Some(index);
+
+        if self.entries(mode).count() > LEADERBOARD_SIZE
+            && let Some((last, _)) = self.entries(mode).last()
+        {
+            self.entries.remove(last);
+
+            if last == index {
+                self.latest = None;
+            }
         }
     }
+}
+
+#[derive(Default, Resource)]
+pub struct Lastscore(pub u16);
 
-    fn new_positions(width: i32, height: i32) -> Vec<Position> {
-        let mut positions = Vec::new();
+/// Time that was played in the current game, without pauses.
+#[derive(Default, Resource)]
+pub struct Playtime(pub Duration);
 
-        for x in 0..width {
-            for y in 0..height {
-                positions.push(Position { x, y });
+#[derive(Default, Resource)]
+pub struct Paused;
+
+/// Command line flag that fixes the seed of the random number generator, e.g. `--seed 42`.
+const SEED_FLAG: &str = "--seed";
+/// Environment variable that fixes the seed of the random number generator.
+const SEED_VARIABLE: &str = "DIPLOPOD_SEED";
+
+/// Seed of the random number generator that places the food. A seed given on the command line or
+/// via environment variable is used for every game, otherwise each game starts with a new random
+/// seed.
+#[derive(Resource)]
+pub struct Seed {
+    /// Seed of the current or the last game.
+    pub value: u64,
+    fixed: Option<u64>,
+}
+
+impl Seed {
+    /// Reads a fixed seed from the `--seed` flag or the `DIPLOPOD_SEED` environment variable. The
+    /// flag takes precedence. Invalid values are logged and ignored.
+    pub fn from_args_or_env() -> Self {
+        let fixed = arg_or_env(SEED_FLAG, SEED_VARIABLE);
+
+        let fixed = fixed.and_then(|seed| match seed.parse() {
+            Ok(seed) => Some(seed),
+            Err(e) => {
+                warn!("Ignoring invalid seed {}: {}", seed, e);
+                None
             }
+        });
+
+        Self {
+            value: fixed.unwrap_or_else(rand::random),
+            fixed,
         }
+    }
+
+    /// Returns the seed for a new game.
+    pub fn next_game(&mut self) -> u64 {
+        self.value = self.fixed.unwrap_or_else(rand::random);
+        self.value
+    }
+}
+
+/// Returns the value of the command line flag, given as `--flag value` or `--flag=value`, or of
+/// the environment variable. The flag takes precedence.
+fn arg_or_env(flag: &str, variable: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
 
-        positions.shuffle(&mut rng());
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
 
-        positions
+        if let Some(value) = arg.strip_prefix(flag).and_then(|s| s.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
     }
 
-    pub fn shuffle(&mut self) {
-        self.positions.shuffle(&mut rng());
+    std::env::var(variable).ok()
+}
+
+/// Command line flag that sets a custom arena size, e.g. `--arena 60x34`.
+const ARENA_FLAG: &str = "--arena";
+/// Environment variable that sets a custom arena size.
+const ARENA_VARIABLE: &str = "DIPLOPOD_ARENA";
+
+/// Size of the arena in tiles, including the surrounding walls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Resource, Serialize, Deserialize)]
+pub struct ArenaSize {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl ArenaSize {
+    pub const SMALL: Self = Self::new(32, 18);
+    pub const MEDIUM: Self = Self::new(48, 27);
+    pub const LARGE: Self = Self::new(64, 36);
+    /// Smallest arena that still has room for all the food.
+    pub const MIN: Self = Self::new(12, 8);
+    pub const MAX: Self = Self::new(160, 90);
+
+    pub const fn new(width: i32, height: i32) -> Self {
+        Self { width, height }
     }
 
-    pub fn remove(&mut self, position: &Position) {
-        self.positions.retain(|&p| p != *position);
+    /// Whether the size is between the smallest and the largest arena.
+    pub fn is_valid(&self) -> bool {
+        (Self::MIN.width..=Self::MAX.width).contains(&self.width)
+            && (Self::MIN.height..=Self::MAX.height).contains(&self.height)
     }
 
-    pub fn remove_all(&mut self, positions: &Vec<Position>) {
-        for position in positions {
-            self.remove(position);
+    /// Name of the arena size as shown in the menu.
+    pub fn name(&self) -> String {
+        match *self {
+            Self::SMALL => "Small".to_string(),
+            Self::MEDIUM => "Medium".to_string(),
+            Self::LARGE => "Large".to_string(),
+            _ => self.to_string(),
         }
     }
+}
 
-    pub fn reset(&mut self) {
-        self.positions = Self::new_positions(self.width, self.height);
+impl Default for ArenaSize {
+    fn default() -> Self {
+        Self::MEDIUM
     }
 }
 
-#[derive(Default, Resource)]
-pub struct LastTailPosition(pub Option<Position>);
+impl std::fmt::Display for ArenaSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
 
-#[derive(Default, Debug, Resource)]
-pub struct TileSize(pub i32);
+/// Parses sizes like `60x34`.
+impl FromStr for ArenaSize {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (width, height) = s
+            .split_once('x')
+            .ok_or_else(|| anyhow!("Arena size {} is not of the form WIDTHxHEIGHT", s))?;
+        let size = Self::new(width.trim().parse()?, height.trim().parse()?);
+
+        if !size.is_valid() {
+            return Err(anyhow!(
+                "Arena size {} is not between {} and {}",
+                size,
+                Self::MIN,
+                Self::MAX
+            ));
+        }
+
+        Ok(size)
+    }
+}
+
+/// Size of the arena that is rendered: the one of the running game or of the edited maze. Unlike
+/// [`ArenaSize`] it isn't chosen by the player, so mazes and campaign levels don't change the
+/// arena size of the menu.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Resource, Default)]
+pub struct ShownArena(pub ArenaSize);
 
+/// The arena sizes that can be chosen in the menu: the presets and the custom size from the
+/// command line or environment or from the settings, if there is one.
 #[derive(Resource)]
-pub struct DefaultFontHandle(pub Handle<Font>);
+pub struct ArenaSizes(pub Vec<ArenaSize>);
 
-#[derive(Default, Resource)]
-pub struct Highscore(pub u16);
+impl ArenaSizes {
+    /// Reads a custom size from the `--arena` flag or the `DIPLOPOD_ARENA` environment variable.
+    /// Invalid values are logged and ignored.
+    pub fn from_args_or_env() -> Self {
+        let mut sizes = vec![ArenaSize::SMALL, ArenaSize::MEDIUM, ArenaSize::LARGE];
 
-#[derive(Default, Resource)]
-pub struct Lastscore(pub u16);
+        if let Some(custom) = arg_or_env(ARENA_FLAG, ARENA_VARIABLE) {
+            match custom.parse() {
+                Ok(custom) if !sizes.contains(&custom) => sizes.push(custom),
+                Ok(_) => (),
+                Err(e) => warn!("Ignoring invalid arena size: {}", e),
+            }
+        }
 
-#[derive(Default, Resource)]
-pub struct Paused;
+        Self(sizes)
+    }
+
+    /// The custom size, which follows the three presets, if there is one, otherwise the medium
+    /// size.
+    pub fn initial(&self) -> ArenaSize {
+        self.custom().unwrap_or(ArenaSize::MEDIUM)
+    }
+
+    /// The custom size from the command line or environment or from the settings, if there is
+    /// one.
+    pub fn custom(&self) -> Option<ArenaSize> {
+        self.0.get(3).copied()
+    }
+
+    /// Replaces the custom size. A custom size that equals one of the presets is dropped.
+    pub fn set_custom(&mut self, custom: ArenaSize) {
+        self.0.truncate(3);
+        if !self.0.contains(&custom) {
+            self.0.push(custom);
+        }
+    }
+
+    pub fn previous(&self, size: &ArenaSize) -> ArenaSize {
+        let index = self.0.iter().position(|s| s == size).unwrap_or(0);
+        self.0[(index + self.0.len() - 1) % self.0.len()]
+    }
+
+    pub fn next(&self, size: &ArenaSize) -> ArenaSize {
+        let index = self.0.iter().position(|s| s == size).map_or(0, |i| i + 1);
+        self.0[index % self.0.len()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(score: u16, mode: GameMode) -> LeaderboardEntry {
+        LeaderboardEntry {
+            initials: "ABC".to_string(),
+            score,
+            length: 1,
+            duration: 0,
+            seed: 0,
+            timestamp: 0,
+            mode,
+        }
+    }
+
+    fn scores(leaderboard: &Leaderboard, mode: GameMode) -> Vec<u16> {
+        leaderboard
+            .entries(mode)
+            .map(|(_, entry)| entry.score)
+            .collect()
+    }
+
+    #[test]
+    fn inserts_entries_ordered_by_score() {
+        let mut leaderboard = Leaderboard::default();
+        for score in [20, 50, 10, 30] {
+            leaderboard.insert(entry(score, GameMode::Classic));
+        }
+        assert_eq!(scores(&leaderboard, GameMode::Classic), [50, 30, 20, 10]);
+        assert_eq!(leaderboard.latest, Some(1));
+        assert_eq!(leaderboard.rank(1), Some(2));
+
+        // a tie is placed behind the entries that were there first
+        let mut tie = entry(30, GameMode::Classic);
+        tie.initials = "NEW".to_string();
+        leaderboard.insert(tie);
+        assert_eq!(leaderboard.latest, Some(2));
+        assert_eq!(leaderboard.entries[2].initials, "NEW");
+    }
+
+    #[test]
+    fn keeps_the_best_entries_of_each_mode() {
+        let mut leaderboard = Leaderboard::default();
+        leaderboard.insert(entry(5, GameMode::Wrap));
+        for score in 1..=LEADERBOARD_SIZE as u16 {
+            leaderboard.insert(entry(score * 10, GameMode::Classic));
+        }
+
+        leaderboard.insert(entry(1, GameMode::Classic));
+        assert_eq!(leaderboard.latest, None);
+        assert_eq!(
+            leaderboard.entries(GameMode::Classic).count(),
+            LEADERBOARD_SIZE
+        );
+
+        leaderboard.insert(entry(55, GameMode::Classic));
+        assert_eq!(
+            leaderboard.entries(GameMode::Classic).count(),
+            LEADERBOARD_SIZE
+        );
+        assert_eq!(scores(&leaderboard, GameMode::Classic).last(), Some(&20));
+        assert_eq!(
+            leaderboard.latest.and_then(|index| leaderboard.rank(index)),
+            Some(6)
+        );
+        assert_eq!(scores(&leaderboard, GameMode::Wrap), [5]);
+        assert_eq!(leaderboard.rank(leaderboard.entries.len() - 1), Some(1));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{
+    GameState,
+    components::OnGameScreen,
+    resources::{Paused, Playtime, Score},
+    simulation::ScoreReason,
+};
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Points for every eaten food.
+pub const FOOD_POINTS: u16 = 1;
+/// Bonus points for eating the super food.
+pub const SUPER_FOOD_POINTS: u16 = 4;
+/// Food that is eaten within this many ticks after the previous one continues the combo.
+pub const COMBO_TICKS: u64 = 20;
+/// Highest bonus that is awarded for a combo.
+pub const MAX_COMBO: u16 = 5;
+/// Points are awarded for every this many ticks the diplopod survives.
+pub const SURVIVAL_TICKS: u64 = 100;
+pub const SURVIVAL_POINTS: u16 = 1;
+
+const HUD_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+
+/// Keeps track of the score of the current game and shows it while playing.
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Game), init)
+            .add_systems(
+                FixedUpdate,
+                track_playtime
+                    .run_if(in_state(GameState::Game))
+                    .run_if(not(resource_exists::<Paused>)),
+            )
+            .add_systems(
+                Update,
+                update_hud
+                    .run_if(in_state(GameState::Game))
+                    .run_if(resource_changed::<Score>),
+            )
+            .init_resource::<Score>()
+            .init_resource::<Playtime>()
+            .add_event::<Scored>();
+    }
+}
+
+/// The player was awarded points.
+#[derive(Event)]
+pub struct Scored(pub u16, pub ScoreReason);
+
+#[derive(Component)]
+struct ScoreText;
+
+pub fn update_score(mut scored_reader: EventReader<Scored>, mut score: ResMut<Score>) {
+    for Scored(points, _) in scored_reader.read() {
+        score.0 = score.0.saturating_add(*points);
+    }
+}
+
+fn track_playtime(time: Res<Time>, mut playtime: ResMut<Playtime>) {
+    playtime.0 += time.delta();
+}
+
+/// Resets the score and the playtime and spawns the HUD that shows the score.
+fn init(mut commands: Commands, mut score: ResMut<Score>, mut playtime: ResMut<Playtime>) {
+    score.0 = 0;
+    playtime.0 = Duration::ZERO;
+
+    commands.spawn((
+        Text::new(format!("Score {}", score.0)),
+        TextFont {
+            font_size: 32.0,
+            ..default()
+        },
+        TextColor(HUD_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            left: Val::Px(10.0),
+            ..default()
+        },
+        ScoreText,
+        OnGameScreen,
+    ));
+}
+
+fn update_hud(score: Res<Score>, mut texts: Query<&mut Text, With<ScoreText>>) {
+    for mut text in texts.iter_mut() {
+        text.0 = format!("Score {}", score.0);
+    }
+}
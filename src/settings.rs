@@ -0,0 +1,601 @@
+use crate::{
+    GameState, TITLE,
+    controls::{Action, Controls},
+    graphics::GraphicsSettings,
+    highscore::{ORGANIZATION, QUALIFIER},
+    level::{MAX_STARTING_LEVEL, StartingLevel},
+    maze::SelectedMaze,
+    resources::{ArenaSize, ArenaSizes},
+    sound::SoundSettings,
+    touch::TouchControls,
+};
+use anyhow::{Result, anyhow};
+use bevy::{
+    color::palettes::css::ANTIQUE_WHITE,
+    prelude::*,
+    window::{MonitorSelection, PrimaryWindow, WindowMode},
+};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::{fmt, fs::File, io::Write};
+
+/// Adds a screen to change the settings and stores them in a config file.
+pub struct SettingsPlugin;
+
+const TITLE_COLOR: Color = Color::Srgba(ANTIQUE_WHITE);
+const BUTTON_TEXT_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const BUTTON_BACKGROUND_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+const BUTTON_SELECTED_BACKGROUND_COLOR: Color = Color::srgb(0.25, 0.25, 0.25);
+
+const CONFIG: &str = "config.ron";
+const VOLUME_STEP: f32 = 0.1;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Settings), setup_settings)
+            .add_systems(
+                Update,
+                (
+                    (keyboard, gamepad, pointer).run_if(in_state(GameState::Settings)),
+                    apply_config.run_if(resource_changed::<Config>),
+                    update_rows.run_if(
+                        resource_changed::<Config>
+                            .or(resource_exists_and_changed::<SettingsScreen>),
+                    ),
+                    save_config
+                        .run_if(resource_changed::<Config>.and(not(resource_added::<Config>))),
+                ),
+            )
+            .add_systems(
+                OnExit(GameState::Settings),
+                (
+                    crate::despawn_screen::<OnSettingsScreen>,
+                    remove_settings_screen,
+                ),
+            )
+            .insert_resource(load_config());
+    }
+}
+
+/// Color of the background behind the arena and the screens.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Theme {
+    #[default]
+    Dark,
+    Midnight,
+    Moss,
+}
+
+impl Theme {
+    fn background(&self) -> Color {
+        match self {
+            Theme::Dark => Color::BLACK,
+            Theme::Midnight => Color::srgb(0.02, 0.03, 0.1),
+            Theme::Moss => Color::srgb(0.03, 0.07, 0.03),
+        }
+    }
+
+    fn previous(&self) -> Self {
+        match self {
+            Theme::Dark => Theme::Moss,
+            Theme::Midnight => Theme::Dark,
+            Theme::Moss => Theme::Midnight,
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            Theme::Dark => Theme::Midnight,
+            Theme::Midnight => Theme::Moss,
+            Theme::Moss => Theme::Dark,
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// The settings that are stored in the config file. The speed and the arena are the ones that were
+/// chosen in the menu or on the settings screen last, new games start with them after the launch.
+/// The custom arena can be chosen besides the presets.
+#[derive(Clone, Debug, PartialEq, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub volume: f32,
+    pub muted: bool,
+    pub speed: u8,
+    pub arena: ArenaSize,
+    pub custom_arena: ArenaSize,
+    pub fullscreen: bool,
+    pub interpolation: bool,
+    pub theme: Theme,
+    pub dpad: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let sound = SoundSettings::default();
+
+        Self {
+            volume: sound.volume,
+            muted: sound.muted,
+            speed: StartingLevel::default().0,
+            arena: ArenaSize::default(),
+            custom_arena: ArenaSize::default(),
+            fullscreen: false,
+            interpolation: GraphicsSettings::default().interpolation,
+            theme: Theme::default(),
+            dpad: TouchControls::default().dpad,
+        }
+    }
+}
+
+/// Whether the field of the config differs from the one that was applied before.
+fn changed<T: PartialEq>(
+    previous: &Option<Config>,
+    config: &Config,
+    field: impl Fn(&Config) -> T,
+) -> bool {
+    previous
+        .as_ref()
+        .is_none_or(|previous| field(previous) != field(config))
+}
+
+/// Applies the fields of the config that changed to the resources and the window they configure.
+/// Everything is applied at the launch, except for the arenas if a custom one was given on the
+/// command line.
+fn apply_config(
+    config: Res<Config>,
+    mut previous: Local<Option<Config>>,
+    mut arena_sizes: ResMut<ArenaSizes>,
+    mut sound_settings: ResMut<SoundSettings>,
+    mut graphics_settings: ResMut<GraphicsSettings>,
+    mut starting_level: ResMut<StartingLevel>,
+    mut arena_size: ResMut<ArenaSize>,
+    mut selected_maze: ResMut<SelectedMaze>,
+    mut clear_color: ResMut<ClearColor>,
+    mut touch_controls: ResMut<TouchControls>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    sound_settings.set_if_neq(SoundSettings {
+        volume: config.volume,
+        muted: config.muted,
+    });
+    graphics_settings.set_if_neq(GraphicsSettings {
+        interpolation: config.interpolation,
+    });
+
+    if changed(&previous, &config, |config| config.speed) {
+        starting_level.set_if_neq(StartingLevel(config.speed));
+    }
+
+    let custom_from_args = previous.is_none() && arena_sizes.custom().is_some();
+
+    if changed(&previous, &config, |config| config.custom_arena) && !custom_from_args {
+        arena_sizes.set_custom(config.custom_arena);
+    }
+
+    if changed(&previous, &config, |config| config.arena) && !custom_from_args {
+        selected_maze.0 = None;
+        arena_size.set_if_neq(config.arena);
+    }
+
+    if changed(&previous, &config, |config| config.fullscreen)
+        && let Ok(mut window) = windows.single_mut()
+    {
+        window.mode = if config.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        };
+    }
+
+    clear_color.0 = config.theme.background();
+    touch_controls.dpad = config.dpad;
+
+    *previous = Some(config.clone());
+}
+
+#[derive(Component)]
+struct OnSettingsScreen;
+
+/// A row of the settings screen.
+#[derive(Clone, Copy, Component, PartialEq)]
+enum Row {
+    Volume,
+    Speed,
+    Arena,
+    CustomWidth,
+    CustomHeight,
+    Fullscreen,
+    Interpolation,
+    Theme,
+    DPad,
+    Controls,
+    Back,
+}
+
+impl Row {
+    const ALL: [Row; 11] = [
+        Row::Volume,
+        Row::Speed,
+        Row::Arena,
+        Row::CustomWidth,
+        Row::CustomHeight,
+        Row::Fullscreen,
+        Row::Interpolation,
+        Row::Theme,
+        Row::DPad,
+        Row::Controls,
+        Row::Back,
+    ];
+}
+
+/// Text of a row of the settings screen.
+#[derive(Component)]
+struct RowText(Row);
+
+/// Index of the selected row of the settings screen.
+#[derive(Default, Resource)]
+struct SettingsScreen {
+    selected: usize,
+}
+
+impl SettingsScreen {
+    fn row(&self) -> Row {
+        Row::ALL[self.selected]
+    }
+}
+
+/// The actions that are used on the settings screen.
+const NAVIGATION: [Action; 6] = [
+    Action::Up,
+    Action::Down,
+    Action::Left,
+    Action::Right,
+    Action::Confirm,
+    Action::Back,
+];
+
+/// Moves between the rows with up and down, changes the setting with left and right and
+/// toggles it or opens the screen of the row with confirm.
+fn keyboard(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    arena_sizes: Res<ArenaSizes>,
+    mut config: ResMut<Config>,
+    mut screen: ResMut<SettingsScreen>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if let Some(action) = NAVIGATION
+        .into_iter()
+        .find(|action| controls.just_released(*action, &keyboard_input))
+    {
+        navigate(
+            action,
+            &arena_sizes,
+            &mut config,
+            &mut screen,
+            &mut game_state,
+        );
+    }
+}
+
+/// Works like the keyboard with the buttons of the gamepads.
+fn gamepad(
+    gamepads: Query<&Gamepad>,
+    controls: Res<Controls>,
+    arena_sizes: Res<ArenaSizes>,
+    mut config: ResMut<Config>,
+    mut screen: ResMut<SettingsScreen>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for gamepad in gamepads.iter() {
+        if let Some(action) = NAVIGATION
+            .into_iter()
+            .find(|action| controls.gamepad_just_released(*action, gamepad))
+        {
+            navigate(
+                action,
+                &arena_sizes,
+                &mut config,
+                &mut screen,
+                &mut game_state,
+            );
+            return;
+        }
+    }
+}
+
+/// Selects the row the mouse pointer hovers over and activates the row that was clicked or
+/// touched.
+fn pointer(
+    interactions: Query<(&Interaction, &Row), Changed<Interaction>>,
+    arena_sizes: Res<ArenaSizes>,
+    mut config: ResMut<Config>,
+    mut screen: ResMut<SettingsScreen>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, row) in interactions.iter() {
+        let Some(index) = Row::ALL.iter().position(|other| other == row) else {
+            continue;
+        };
+
+        if *interaction != Interaction::None && screen.selected != index {
+            screen.selected = index;
+        }
+
+        if *interaction == Interaction::Pressed {
+            navigate(
+                Action::Confirm,
+                &arena_sizes,
+                &mut config,
+                &mut screen,
+                &mut game_state,
+            );
+        }
+    }
+}
+
+/// Changes the settings screen or the config according to the action. The resources are only
+/// changed, and the config only saved, if the action does something.
+fn navigate(
+    action: Action,
+    arena_sizes: &ArenaSizes,
+    config: &mut ResMut<Config>,
+    screen: &mut ResMut<SettingsScreen>,
+    game_state: &mut NextState<GameState>,
+) {
+    let row = screen.row();
+
+    match (action, row) {
+        (Action::Up, _) => {
+            screen.selected = (screen.selected + Row::ALL.len() - 1) % Row::ALL.len()
+        }
+        (Action::Down, _) => screen.selected = (screen.selected + 1) % Row::ALL.len(),
+        (Action::Left, Row::Volume) => change_volume(config, -VOLUME_STEP),
+        (Action::Right, Row::Volume) => change_volume(config, VOLUME_STEP),
+        (Action::Confirm, Row::Volume) => config.muted = !config.muted,
+        (Action::Left, Row::Speed) => config.speed = StartingLevel(config.speed).previous(),
+        (Action::Right | Action::Confirm, Row::Speed) => {
+            config.speed = StartingLevel(config.speed).next();
+        }
+        (Action::Left, Row::Arena) => config.arena = arena_sizes.previous(&config.arena),
+        (Action::Right | Action::Confirm, Row::Arena) => {
+            config.arena = arena_sizes.next(&config.arena);
+        }
+        (Action::Left, Row::CustomWidth) => change_custom_arena(config, IVec2::NEG_X),
+        (Action::Right, Row::CustomWidth) => change_custom_arena(config, IVec2::X),
+        (Action::Left, Row::CustomHeight) => change_custom_arena(config, IVec2::NEG_Y),
+        (Action::Right, Row::CustomHeight) => change_custom_arena(config, IVec2::Y),
+        (Action::Left | Action::Right | Action::Confirm, Row::Fullscreen) => {
+            config.fullscreen = !config.fullscreen;
+        }
+        (Action::Left | Action::Right | Action::Confirm, Row::Interpolation) => {
+            config.interpolation = !config.interpolation;
+        }
+        (Action::Left, Row::Theme) => config.theme = config.theme.previous(),
+        (Action::Right | Action::Confirm, Row::Theme) => config.theme = config.theme.next(),
+        (Action::Left | Action::Right | Action::Confirm, Row::DPad) => {
+            config.dpad = !config.dpad;
+        }
+        (Action::Confirm, Row::Controls) => game_state.set(GameState::Controls),
+        (Action::Confirm, Row::Back) | (Action::Back, _) => game_state.set(GameState::Menu),
+        _ => (),
+    }
+}
+
+fn change_volume(config: &mut Config, step: f32) {
+    // round to avoid that the steps accumulate floating point errors
+    config.volume = ((config.volume + step) * 10.0).round() / 10.0;
+    config.volume = config.volume.clamp(0.0, 1.0);
+}
+
+/// Changes the width or the height of the custom arena within the allowed sizes and plays in it.
+fn change_custom_arena(config: &mut ResMut<Config>, delta: IVec2) {
+    let custom = ArenaSize::new(
+        (config.custom_arena.width + delta.x).clamp(ArenaSize::MIN.width, ArenaSize::MAX.width),
+        (config.custom_arena.height + delta.y).clamp(ArenaSize::MIN.height, ArenaSize::MAX.height),
+    );
+
+    if custom != config.custom_arena {
+        config.custom_arena = custom;
+        config.arena = custom;
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "On" } else { "Off" }
+}
+
+fn row_text(row: Row, config: &Config) -> String {
+    match row {
+        Row::Volume if config.muted => "< Volume muted >".to_string(),
+        Row::Volume => format!("< Volume {:.0}% >", config.volume * 100.0),
+        Row::Speed => format!("< Speed {} >", config.speed),
+        Row::Arena => format!("< Arena {} >", config.arena.name()),
+        Row::CustomWidth => format!("< Custom width {} >", config.custom_arena.width),
+        Row::CustomHeight => format!("< Custom height {} >", config.custom_arena.height),
+        Row::Fullscreen => format!("< Fullscreen {} >", on_off(config.fullscreen)),
+        Row::Interpolation => format!("< Smooth movement {} >", on_off(config.interpolation)),
+        Row::Theme => format!("< Theme {} >", config.theme),
+        Row::DPad => format!("< Touch D-pad {} >", on_off(config.dpad)),
+        Row::Controls => "Controls".to_string(),
+        Row::Back => "Back".to_string(),
+    }
+}
+
+fn update_rows(
+    config: Res<Config>,
+    screen: Option<Res<SettingsScreen>>,
+    mut texts: Query<(&mut Text, &RowText)>,
+    mut backgrounds: Query<(&mut BackgroundColor, &Row)>,
+) {
+    let Some(screen) = screen else {
+        return;
+    };
+
+    for (mut text, row_text_of) in texts.iter_mut() {
+        text.0 = row_text(row_text_of.0, &config);
+    }
+
+    for (mut background_color, row) in backgrounds.iter_mut() {
+        background_color.0 = if *row == screen.row() {
+            BUTTON_SELECTED_BACKGROUND_COLOR
+        } else {
+            BUTTON_BACKGROUND_COLOR
+        };
+    }
+}
+
+fn remove_settings_screen(mut commands: Commands) {
+    commands.remove_resource::<SettingsScreen>();
+}
+
+/// Creates the UI of the settings screen with a row per setting followed by the controls.
+fn setup_settings(mut commands: Commands, config: Res<Config>) {
+    let screen = SettingsScreen::default();
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            OnSettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Settings"),
+                        TextFont {
+                            font_size: 96.0,
+                            ..default()
+                        },
+                        TextColor(TITLE_COLOR),
+                        Node {
+                            margin: UiRect::all(Val::Px(20.0)),
+                            ..default()
+                        },
+                    ));
+
+                    for (index, row) in Row::ALL.into_iter().enumerate() {
+                        parent
+                            .spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(480.0),
+                                    height: Val::Px(36.0),
+                                    margin: UiRect::all(Val::Px(4.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                BackgroundColor(if index == screen.selected {
+                                    BUTTON_SELECTED_BACKGROUND_COLOR
+                                } else {
+                                    BUTTON_BACKGROUND_COLOR
+                                }),
+                                row,
+                            ))
+                            .with_children(|parent| {
+                                parent.spawn((
+                                    Text::new(row_text(row, &config)),
+                                    TextFont {
+                                        font_size: 28.0,
+                                        ..default()
+                                    },
+                                    TextColor(BUTTON_TEXT_COLOR),
+                                    RowText(row),
+                                ));
+                            });
+                    }
+                });
+        });
+
+    commands.insert_resource(screen);
+}
+
+/// Load the config from platform specific data directory (e.g.
+/// `$HOME/.local/share/diplopod/config.ron`), next to the leaderboard. Settings that are missing
+/// in the file keep their defaults. If the file is invalid or inaccessible the default config is
+/// used.
+fn load_config() -> Config {
+    match read_config_from_file() {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("{}", e);
+            Config::default()
+        }
+    }
+}
+
+/// Reads the config from the platform specific data directory. Values that are out of range are
+/// clamped. Without a config file the default config is used.
+fn read_config_from_file() -> Result<Config> {
+    if let Some(projects_dirs) = ProjectDirs::from(QUALIFIER, ORGANIZATION, TITLE) {
+        let data_dir = projects_dirs.data_dir();
+        let mut config: Config = match std::fs::read_to_string(data_dir.join(CONFIG)) {
+            Ok(content) => ron::from_str(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+            Err(e) => return Err(e.into()),
+        };
+
+        config.volume = config.volume.clamp(0.0, 1.0);
+        config.speed = config.speed.clamp(1, MAX_STARTING_LEVEL);
+        if !config.arena.is_valid() {
+            config.arena = ArenaSize::default();
+        }
+        if !config.custom_arena.is_valid() {
+            config.custom_arena = ArenaSize::default();
+        }
+
+        Ok(config)
+    } else {
+        Err(anyhow!(
+            "Unable to retrieve path to data directory. Config can't be read."
+        ))
+    }
+}
+
+/// Save the config to platform specific data directory (e.g.
+/// `$HOME/.local/share/diplopod/config.ron`). Errors will be logged but otherwise ignored.
+fn save_config(config: Res<Config>) {
+    if let Err(e) = write_config_to_file(&config) {
+        warn!("{}", e)
+    }
+}
+
+/// Writes the config to the platform specific data directory. The file and all necessary
+/// directories are created by the function.
+fn write_config_to_file(config: &Config) -> Result<()> {
+    if let Some(projects_dirs) = ProjectDirs::from(QUALIFIER, ORGANIZATION, TITLE) {
+        let data_dir = projects_dirs.data_dir();
+        std::fs::create_dir_all(data_dir)?;
+
+        let mut file = File::create(data_dir.join(CONFIG))?;
+        write!(
+            file,
+            "{}",
+            ron::ser::to_string_pretty(config, ron::ser::PrettyConfig::default())?
+        )?;
+
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Unable to retrieve path to data directory. Config can't be written."
+        ))
+    }
+}
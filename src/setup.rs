@@ -1,33 +1,67 @@
 use std::cmp;
 
-use crate::resources::{DefaultFontHandle, TileSize};
+use crate::{
+    components::GameState,
+    resources::{ArenaSize, DefaultFontHandle, ShownArena, TileSize},
+};
 use bevy::{prelude::*, window::PrimaryWindow};
 
 pub fn setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    arena: Res<ShownArena>,
+    windows: Query<&Window, With<PrimaryWindow>>,
 ) {
     let font = asset_server.load("fonts/AllertaStencil-Regular.ttf");
     commands.insert_resource(DefaultFontHandle(font));
 
-    let Ok(mut window) = windows.single_mut() else {
+    let Ok(window) = windows.single() else {
         return;
     };
 
-    window.cursor_options.visible = false;
+    commands.insert_resource(TileSize(tile_size(window, &arena.0)));
+}
 
+/// Returns the largest tile size that fits the whole arena into the window.
+fn tile_size(window: &Window, arena_size: &ArenaSize) -> i32 {
     let window_width = window.width() as i32;
     let window_height = window.height() as i32;
     info!(window_width);
     info!(window_height);
 
-    let tile_size = cmp::min(
-        window_width / crate::ARENA_WIDTH,
-        window_height / crate::ARENA_HEIGHT,
+    let tile_size = cmp::max(
+        cmp::min(
+            window_width / arena_size.width,
+            window_height / arena_size.height,
+        ),
+        1,
     );
     info!(tile_size);
-    commands.insert_resource(TileSize(tile_size));
+    tile_size
+}
+
+/// Recomputes the tile size when the window is resized or another arena is shown. The
+/// arena stays centered and the area that isn't covered by it is left blank.
+pub fn resize(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    arena: Res<ShownArena>,
+    mut tile_size: ResMut<TileSize>,
+) {
+    let Ok(window) = windows.single() else {
+        return;
+    };
+
+    tile_size.set_if_neq(TileSize(self::tile_size(window, &arena.0)));
+}
+
+/// Shows the cursor on every screen to point at the buttons, but hides it during the game.
+pub fn update_cursor_visibility(
+    state: Res<State<GameState>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if let Ok(mut window) = windows.single_mut() {
+        window.cursor_options.visible = *state.get() != GameState::Game;
+    }
 }
 
 pub fn set_default_font(
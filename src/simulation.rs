@@ -0,0 +1,1300 @@
+pub mod advance;
+mod free_positions;
+pub mod setup;
+
+use crate::{
+    GameState,
+    campaign::EXIT_FOOD,
+    components::Position,
+    food::{
+        AMOUNT_OF_FOOD, AMOUNT_OF_POISON, ANTIDOTE_DURATION, ANTIDOTE_INTERVAL,
+        SUPER_FOOD_DURATION, SUPER_FOOD_GROWTH, SUPER_FOOD_INTERVAL,
+    },
+    maze::Maze,
+    score::{
+        COMBO_TICKS, FOOD_POINTS, MAX_COMBO, SUPER_FOOD_POINTS, SURVIVAL_POINTS, SURVIVAL_TICKS,
+    },
+};
+use anyhow::anyhow;
+use bevy::prelude::*;
+use rand::{SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use std::{collections::VecDeque, str::FromStr};
+
+pub use free_positions::FreePositions;
+
+/// Adds the headless [`GameSim`] that owns the rules of the game. The other plugins only render
+/// its state.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Game), setup::init)
+            .init_resource::<GameMode>();
+    }
+}
+
+/// The rules for the border of the arena.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Resource, Serialize, Deserialize)]
+pub enum GameMode {
+    /// The arena is surrounded by walls that end the game.
+    #[default]
+    Classic,
+    /// There are no walls and the head reappears on the opposite edge of the arena.
+    Wrap,
+    /// The mazes are played one after another. Eating enough food opens an exit to the next one.
+    Campaign,
+    /// Two diplopods play against each other in the walled arena. The last one that survives
+    /// wins the round.
+    Versus,
+}
+
+impl GameMode {
+    pub fn previous(&self) -> Self {
+        match self {
+            GameMode::Classic => GameMode::Versus,
+            GameMode::Wrap => GameMode::Classic,
+            GameMode::Campaign => GameMode::Wrap,
+            GameMode::Versus => GameMode::Campaign,
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            GameMode::Classic => GameMode::Wrap,
+            GameMode::Wrap => GameMode::Campaign,
+            GameMode::Campaign => GameMode::Versus,
+            GameMode::Versus => GameMode::Classic,
+        }
+    }
+}
+
+impl std::fmt::Display for GameMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameMode::Classic => write!(f, "Classic"),
+            GameMode::Wrap => write!(f, "Wrap"),
+            GameMode::Campaign => write!(f, "Campaign"),
+            GameMode::Versus => write!(f, "Versus"),
+        }
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "Classic" => Ok(GameMode::Classic),
+            "Wrap" => Ok(GameMode::Wrap),
+            "Campaign" => Ok(GameMode::Campaign),
+            "Versus" => Ok(GameMode::Versus),
+            _ => Err(anyhow!("Unknown game mode {}", s)),
+        }
+    }
+}
+
+/// The kinds of food that can be placed in the arena.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FoodKind {
+    /// Lets the diplopod grow.
+    Normal,
+    /// Shrinks the diplopod, or ends the game if only the head is left, unless an antidote is
+    /// active.
+    Poison,
+    /// Turns poison into normal food for a limited number of ticks.
+    Antidote,
+    /// Lets the diplopod grow by several segments but disappears after a limited number of
+    /// ticks.
+    Super,
+}
+
+/// The reasons the player is awarded points for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScoreReason {
+    /// Food was eaten.
+    Food,
+    /// Super food was eaten.
+    Bonus,
+    /// Food was eaten shortly after the previous one.
+    Combo,
+    /// The diplopod survived for a while.
+    Survival,
+}
+
+/// Something that happened during a single tick of the simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimEvent {
+    /// The head ate the food at the given position. Poison that was eaten while an antidote is
+    /// active is reported as normal food.
+    Ate(Position, FoodKind),
+    /// The diplopod grew by the given number of segments.
+    Grew(u8),
+    /// The diplopod lost the given number of segments at its tail.
+    Shrank(u8),
+    /// New food was placed at the given position.
+    FoodSpawned(Position, FoodKind),
+    /// The food at the given position disappeared without being eaten.
+    FoodExpired(Position, FoodKind),
+    /// The player was awarded the given number of points.
+    Scored(u16, ScoreReason),
+    /// The exit to the next level of the campaign was opened at the given position.
+    ExitOpened(Position),
+    /// The head went through the exit. The walls, the food and the diplopod were replaced by the
+    /// ones of the given next level, unless it was the last level and the game is over.
+    LevelCompleted(usize),
+    /// The head hit a wall, the border of the arena or a diplopod. In the versus mode the round
+    /// is over, see [`GameSim::winner`].
+    GameOver,
+}
+
+/// Deterministic state of a single game that can be advanced without a window, e.g. from tests,
+/// bots or replays.
+///
+/// The arena is `width` times `height` tiles. In the classic mode there is a wall along its
+/// border, in the wrap mode the head leaves the arena on one edge and enters it on the opposite
+/// one. The segments are ordered from head to tail. All random placement draws from a generator
+/// created from `seed`, so the same seed and the same inputs always result in the same game.
+///
+/// There is a single diplopod, except for the versus mode with one diplopod per player. The
+/// accessors without a player refer to the first diplopod.
+#[derive(Clone, Resource)]
+pub struct GameSim {
+    width: i32,
+    height: i32,
+    mode: GameMode,
+    seed: u64,
+    rng: StdRng,
+    tick: u64,
+    diplopods: Vec<Diplopod>,
+    food: Vec<(Position, FoodKind)>,
+    walls: Vec<Position>,
+    free_positions: FreePositions,
+    score: u16,
+    combo: u16,
+    last_eaten_tick: Option<u64>,
+    survived_ticks: u64,
+    eaten_food: u32,
+    antidote_ticks: u64,
+    super_food_countdown: u64,
+    super_food_ticks: u64,
+    campaign: Option<Campaign>,
+    losers: Vec<usize>,
+    game_over: bool,
+}
+
+/// Number of turns that are buffered per diplopod, one is applied per tick.
+const MAX_TURNS: usize = 2;
+
+/// The direction, the queued turns and the segments of a single diplopod.
+#[derive(Clone)]
+struct Diplopod {
+    direction: IVec2,
+    turns: VecDeque<IVec2>,
+    segments: Vec<Position>,
+}
+
+impl Diplopod {
+    fn new(head: Position) -> Self {
+        Self {
+            direction: IVec2::ZERO,
+            turns: VecDeque::with_capacity(MAX_TURNS),
+            segments: vec![head],
+        }
+    }
+
+    /// Whether the diplopod can head in the direction after moving in `last`. It has to be a
+    /// single tile along one of the axes and must not lead back into the segment behind the head.
+    fn can_turn(&self, last: IVec2, direction: IVec2) -> bool {
+        direction.x.abs() + direction.y.abs() == 1
+            && (self.segments.len() == 1 || direction != -last)
+    }
+
+    /// Queues a turn in the direction. Invalid directions, the direction the diplopod already
+    /// moves in after the queued turns and turns beyond the buffer are ignored.
+    fn turn(&mut self, direction: IVec2) {
+        let last = self.turns.back().copied().unwrap_or(self.direction);
+        if direction != last && self.turns.len() < MAX_TURNS && self.can_turn(last, direction) {
+            self.turns.push_back(direction);
+        }
+    }
+
+    /// Applies the next queued turn, if it is still valid.
+    fn apply_turn(&mut self) {
+        if let Some(direction) = self.turns.pop_front()
+            && self.can_turn(self.direction, direction)
+        {
+            self.direction = direction;
+        }
+    }
+}
+
+/// The mazes of a campaign, the index of the first and the current one and the exit.
+#[derive(Clone)]
+struct Campaign {
+    mazes: Vec<Maze>,
+    start: usize,
+    level: usize,
+    eaten_food: u32,
+    exit: Option<Position>,
+}
+
+impl GameSim {
+    /// Creates a new game with the border walls of the mode, the head in the center of the arena
+    /// and the initial food.
+    pub fn new(width: i32, height: i32, mode: GameMode, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let free_positions = match mode {
+            GameMode::Classic | GameMode::Campaign | GameMode::Versus => {
+                FreePositions::new(width - 1, height - 1, &mut rng)
+            }
+            GameMode::Wrap => FreePositions::new(width, height, &mut rng),
+        };
+        let head = Position {
+            x: width / 2,
+            y: height / 2,
+        };
+
+        let mut sim = Self::empty(width, height, mode, seed, rng, free_positions, head);
+
+        if mode != GameMode::Wrap {
+            sim.init_walls();
+        }
+        sim.init_food(AMOUNT_OF_FOOD);
+
+        sim
+    }
+
+    /// Creates a new game in the maze. The food is only placed on tiles that the head can reach and
+    /// that are in the food zones of the maze.
+    pub fn with_maze(maze: &Maze, mode: GameMode, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let free_positions = FreePositions::from_positions(
+            maze.width,
+            maze.height,
+            maze.food_positions(mode == GameMode::Wrap),
+            &mut rng,
+        );
+
+        let mut sim = Self::empty(
+            maze.width,
+            maze.height,
+            mode,
+            seed,
+            rng,
+            free_positions,
+            maze.start,
+        );
+        sim.diplopods[0].direction = maze.direction;
+        sim.walls = maze.walls.clone();
+        sim.init_food(maze.food);
+
+        sim
+    }
+
+    /// Creates a new campaign that starts in the maze of the given level.
+    pub fn campaign(mazes: Vec<Maze>, level: usize, seed: u64) -> Self {
+        let mut sim = Self::with_maze(&mazes[level], GameMode::Campaign, seed);
+        sim.campaign = Some(Campaign {
+            mazes,
+            start: level,
+            level,
+            eaten_food: 0,
+            exit: None,
+        });
+
+        sim
+    }
+
+    /// Creates a new round of the versus mode. The players start on the left and the right half
+    /// of the walled arena.
+    pub fn versus(width: i32, height: i32, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let free_positions = FreePositions::new(width - 1, height - 1, &mut rng);
+        let head = Position {
+            x: width / 3,
+            y: height / 2,
+        };
+
+        let mut sim = Self::empty(
+            width,
+            height,
+            GameMode::Versus,
+            seed,
+            rng,
+            free_positions,
+            head,
+        );
+        sim.diplopods.push(Diplopod::new(Position {
+            x: width - 1 - width / 3,
+            y: height / 2,
+        }));
+
+        sim.init_walls();
+        sim.init_food(AMOUNT_OF_FOOD);
+
+        sim
+    }
+
+    fn empty(
+        width: i32,
+        height: i32,
+        mode: GameMode,
+        seed: u64,
+        rng: StdRng,
+        free_positions: FreePositions,
+        head: Position,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            mode,
+            seed,
+            rng,
+            tick: 0,
+            diplopods: vec![Diplopod::new(head)],
+            food: Vec::new(),
+            walls: Vec::new(),
+            free_positions,
+            score: 0,
+            combo: 0,
+            last_eaten_tick: None,
+            survived_ticks: 0,
+            eaten_food: 0,
+            antidote_ticks: 0,
+            super_food_countdown: SUPER_FOOD_INTERVAL,
+            super_food_ticks: 0,
+            campaign: None,
+            losers: Vec::new(),
+            game_over: false,
+        }
+    }
+
+    fn init_walls(&mut self) {
+        for x in 0..self.width {
+            self.walls.push(Position { x, y: 0 });
+            self.walls.push(Position {
+                x,
+                y: self.height - 1,
+            });
+        }
+
+        for y in 1..self.height - 1 {
+            self.walls.push(Position { x: 0, y });
+            self.walls.push(Position {
+                x: self.width - 1,
+                y,
+            });
+        }
+
+        self.free_positions.remove_all(&self.walls);
+    }
+
+    fn init_food(&mut self, amount: u32) {
+        for _ in 0..amount {
+            if self.place_food(FoodKind::Normal).is_none() {
+                break;
+            }
+        }
+
+        for _ in 0..AMOUNT_OF_POISON {
+            if self.place_food(FoodKind::Poison).is_none() {
+                break;
+            }
+        }
+    }
+
+    /// Returns a random free position that is not occupied by a diplopod.
+    fn free_position(&self) -> Option<Position> {
+        let mut position_candidates = self.free_positions.clone();
+        for diplopod in &self.diplopods {
+            position_candidates.remove_all(&diplopod.segments);
+        }
+
+        position_candidates.positions.pop()
+    }
+
+    /// Places a single food on a random free position that is not occupied by a diplopod.
+    fn place_food(&mut self, kind: FoodKind) -> Option<Position> {
+        let pos = self.free_position()?;
+        self.free_positions.remove(&pos);
+        self.food.push((pos, kind));
+
+        Some(pos)
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Number of ticks that have been simulated so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn direction(&self) -> IVec2 {
+        self.diplopods[0].direction
+    }
+
+    pub fn head(&self) -> Position {
+        self.diplopods[0].segments[0]
+    }
+
+    pub fn segments(&self) -> &[Position] {
+        &self.diplopods[0].segments
+    }
+
+    /// Number of diplopods, one per player.
+    pub fn players(&self) -> usize {
+        self.diplopods.len()
+    }
+
+    pub fn player_segments(&self, player: usize) -> &[Position] {
+        &self.diplopods[player].segments
+    }
+
+    /// The player that won the round of the versus mode, or `None` if it isn't over yet or all
+    /// players lost at the same time.
+    pub fn winner(&self) -> Option<usize> {
+        if !self.game_over || self.mode != GameMode::Versus {
+            return None;
+        }
+
+        let mut survivors = (0..self.players()).filter(|player| !self.losers.contains(player));
+        match (survivors.next(), survivors.next()) {
+            (Some(winner), None) => Some(winner),
+            _ => None,
+        }
+    }
+
+    pub fn food(&self) -> &[(Position, FoodKind)] {
+        &self.food
+    }
+
+    pub fn walls(&self) -> &[Position] {
+        &self.walls
+    }
+
+    pub fn score(&self) -> u16 {
+        self.score
+    }
+
+    /// Whether an antidote was eaten recently, so that poison acts like normal food.
+    pub fn is_antidote_active(&self) -> bool {
+        self.antidote_ticks > 0
+    }
+
+    /// Number of ticks until the super food disappears or zero if there is none.
+    pub fn super_food_ticks(&self) -> u64 {
+        self.super_food_ticks
+    }
+
+    /// Index of the level the campaign started with.
+    pub fn campaign_start(&self) -> Option<usize> {
+        self.campaign.as_ref().map(|campaign| campaign.start)
+    }
+
+    /// Index of the current level of the campaign.
+    pub fn campaign_level(&self) -> Option<usize> {
+        self.campaign.as_ref().map(|campaign| campaign.level)
+    }
+
+    /// Position of the exit to the next level of the campaign, if it was opened already.
+    pub fn exit(&self) -> Option<Position> {
+        self.campaign.as_ref().and_then(|campaign| campaign.exit)
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over
+    }
+
+    /// Queues a turn of the diplopod of the player in the direction. One queued turn is applied
+    /// per tick, so two quick turns are not lost. Diagonal directions, reversals into the segment
+    /// behind the head and turns beyond the buffer are ignored.
+    pub fn turn(&mut self, player: usize, direction: IVec2) {
+        if let Some(diplopod) = self.diplopods.get_mut(player) {
+            diplopod.turn(direction);
+        }
+    }
+
+    /// Advances the game by one tick. A non-zero `direction` is queued like a
+    /// [`turn`](GameSim::turn) before the next queued turn is applied. Nothing moves until the
+    /// first direction was given and after the game is over.
+    pub fn step(&mut self, direction: IVec2) -> Vec<SimEvent> {
+        self.step_players(&[direction])
+    }
+
+    /// Advances the game by one tick with a direction per player, see [`GameSim::step`]. All
+    /// diplopods move at the same time. A head that hits a wall, the border of the arena, a
+    /// segment of any diplopod or another head loses.
+    pub fn step_players(&mut self, directions: &[IVec2]) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+
+        if self.game_over {
+            return events;
+        }
+
+        self.tick += 1;
+
+        for (diplopod, direction) in self.diplopods.iter_mut().zip(directions) {
+            if *direction != IVec2::ZERO {
+                diplopod.turn(*direction);
+            }
+        }
+
+        for diplopod in &mut self.diplopods {
+            diplopod.apply_turn();
+        }
+
+        let new_heads: Vec<Option<Position>> = self
+            .diplopods
+            .iter()
+            .map(|diplopod| (diplopod.direction != IVec2::ZERO).then(|| self.next_head(diplopod)))
+            .collect();
+
+        if new_heads.iter().all(Option::is_none) {
+            return events;
+        }
+
+        let losers: Vec<usize> = new_heads
+            .iter()
+            .enumerate()
+            .filter_map(|(player, new_head)| {
+                let new_head = (*new_head)?;
+                let head_on = new_heads
+                    .iter()
+                    .enumerate()
+                    .any(|(other, head)| other != player && *head == Some(new_head));
+
+                (head_on || self.is_blocked(&new_head)).then_some(player)
+            })
+            .collect();
+
+        if !losers.is_empty() {
+            self.lose(losers, &mut events);
+            return events;
+        }
+
+        if let Some(new_head) = new_heads[0]
+            && self.exit() == Some(new_head)
+        {
+            self.complete_level(&mut events);
+            return events;
+        }
+
+        self.survived_ticks += 1;
+        if self.survived_ticks.is_multiple_of(SURVIVAL_TICKS) {
+            self.award(SURVIVAL_POINTS, ScoreReason::Survival, &mut events);
+        }
+
+        let antidote_active = self.is_antidote_active();
+        self.antidote_ticks = self.antidote_ticks.saturating_sub(1);
+
+        for (player, new_head) in new_heads.into_iter().enumerate() {
+            let Some(new_head) = new_head else {
+                continue;
+            };
+
+            let segments = &mut self.diplopods[player].segments;
+            let last_tail_position = *segments.last().unwrap();
+            segments.rotate_right(1);
+            segments[0] = new_head;
+
+            self.eat(
+                player,
+                new_head,
+                last_tail_position,
+                antidote_active,
+                &mut events,
+            );
+            if self.game_over {
+                return events;
+            }
+        }
+
+        self.update_super_food(&mut events);
+
+        events
+    }
+
+    /// Returns the position the head of the diplopod moves to next.
+    fn next_head(&self, diplopod: &Diplopod) -> Position {
+        let head = diplopod.segments[0];
+        let mut new_head = Position {
+            x: head.x + diplopod.direction.x,
+            y: head.y + diplopod.direction.y,
+        };
+
+        if self.mode == GameMode::Wrap {
+            new_head.x = new_head.x.rem_euclid(self.width);
+            new_head.y = new_head.y.rem_euclid(self.height);
+        }
+
+        new_head
+    }
+
+    /// Whether a head at the position hits a wall, the border of the arena or a diplopod.
+    fn is_blocked(&self, pos: &Position) -> bool {
+        let outside = !(0..self.width).contains(&pos.x) || !(0..self.height).contains(&pos.y);
+
+        outside
+            || self.walls.contains(pos)
+            || self
+                .diplopods
+                .iter()
+                .any(|diplopod| diplopod.segments.contains(pos))
+    }
+
+    /// Ends the game, or the round of the versus mode, because the players lost.
+    fn lose(&mut self, losers: Vec<usize>, events: &mut Vec<SimEvent>) {
+        self.losers = losers;
+        self.game_over = true;
+        events.push(SimEvent::GameOver);
+    }
+
+    /// Eats the food at the new head of the player, if there is any.
+    fn eat(
+        &mut self,
+        player: usize,
+        new_head: Position,
+        last_tail_position: Position,
+        antidote_active: bool,
+        events: &mut Vec<SimEvent>,
+    ) {
+        let Some(index) = self.food.iter().position(|&(pos, _)| pos == new_head) else {
+            return;
+        };
+
+        let (_, kind) = self.food.swap_remove(index);
+        self.free_positions.positions.push(new_head);
+        self.free_positions.shuffle(&mut self.rng);
+
+        if kind == FoodKind::Poison && antidote_active {
+            events.push(SimEvent::Ate(new_head, FoodKind::Normal));
+        } else {
+            events.push(SimEvent::Ate(new_head, kind));
+        }
+
+        match kind {
+            FoodKind::Normal => {
+                self.eat_food(player, last_tail_position, 1, events);
+                self.spawn_food(FoodKind::Normal, events);
+            }
+            FoodKind::Poison if antidote_active => {
+                self.eat_food(player, last_tail_position, 1, events);
+                self.spawn_food(FoodKind::Poison, events);
+            }
+            FoodKind::Poison => {
+                if self.diplopods[player].segments.len() == 1 {
+                    self.lose(vec![player], events);
+                    return;
+                }
+
+                self.shrink(player, 1);
+                events.push(SimEvent::Shrank(1));
+                self.spawn_food(FoodKind::Poison, events);
+            }
+            FoodKind::Antidote => {
+                self.antidote_ticks = ANTIDOTE_DURATION;
+            }
+            FoodKind::Super => {
+                self.super_food_ticks = 0;
+                self.eat_food(player, last_tail_position, SUPER_FOOD_GROWTH, events);
+                self.award(SUPER_FOOD_POINTS, ScoreReason::Bonus, events);
+            }
+        }
+    }
+
+    /// Places the super food every `SUPER_FOOD_INTERVAL` ticks and removes it again when it
+    /// wasn't eaten within `SUPER_FOOD_DURATION` ticks.
+    fn update_super_food(&mut self, events: &mut Vec<SimEvent>) {
+        if let Some(index) = self
+            .food
+            .iter()
+            .position(|&(_, kind)| kind == FoodKind::Super)
+        {
+            self.super_food_ticks = self.super_food_ticks.saturating_sub(1);
+
+            if self.super_food_ticks == 0 {
+                let (pos, kind) = self.food.swap_remove(index);
+                self.free_positions.positions.push(pos);
+                self.free_positions.shuffle(&mut self.rng);
+                events.push(SimEvent::FoodExpired(pos, kind));
+            }
+
+            return;
+        }
+
+        self.super_food_countdown = self.super_food_countdown.saturating_sub(1);
+
+        if self.super_food_countdown == 0 {
+            self.super_food_countdown = SUPER_FOOD_INTERVAL;
+            self.super_food_ticks = SUPER_FOOD_DURATION;
+            self.spawn_food(FoodKind::Super, events);
+        }
+    }
+
+    /// Grows the diplopod of the player by `amount` segments, awards the points for the food and
+    /// places an antidote after every `ANTIDOTE_INTERVAL` eaten food, unless there is one already.
+    fn eat_food(
+        &mut self,
+        player: usize,
+        last_tail_position: Position,
+        amount: u8,
+        events: &mut Vec<SimEvent>,
+    ) {
+        self.grow(player, last_tail_position, amount);
+        events.push(SimEvent::Grew(amount));
+
+        self.award(FOOD_POINTS, ScoreReason::Food, events);
+
+        if self
+            .last_eaten_tick
+            .is_some_and(|tick| self.tick - tick <= COMBO_TICKS)
+        {
+            self.combo = (self.combo + 1).min(MAX_COMBO);
+            self.award(self.combo, ScoreReason::Combo, events);
+        } else {
+            self.combo = 0;
+        }
+        self.last_eaten_tick = Some(self.tick);
+
+        self.update_exit(events);
+
+        self.eaten_food += 1;
+        if self.eaten_food.is_multiple_of(ANTIDOTE_INTERVAL)
+            && !self
+                .food
+                .iter()
+                .any(|&(_, kind)| kind == FoodKind::Antidote)
+        {
+            self.spawn_food(FoodKind::Antidote, events);
+        }
+    }
+
+    /// Opens the exit of the campaign level when `EXIT_FOOD` food were eaten on it.
+    fn update_exit(&mut self, events: &mut Vec<SimEvent>) {
+        let Some(campaign) = &mut self.campaign else {
+            return;
+        };
+
+        campaign.eaten_food += 1;
+        if campaign.exit.is_some() || campaign.eaten_food < EXIT_FOOD {
+            return;
+        }
+
+        let Some(pos) = self.free_position() else {
+            return;
+        };
+        self.free_positions.remove(&pos);
+        if let Some(campaign) = &mut self.campaign {
+            campaign.exit = Some(pos);
+        }
+        events.push(SimEvent::ExitOpened(pos));
+    }
+
+    /// Replaces the arena with the maze of the next campaign level, or ends the game after the
+    /// last one. The score and the tick count are kept.
+    fn complete_level(&mut self, events: &mut Vec<SimEvent>) {
+        let Some(campaign) = &mut self.campaign else {
+            return;
+        };
+
+        campaign.level += 1;
+        let level = campaign.level;
+        events.push(SimEvent::LevelCompleted(level));
+
+        let Some(maze) = campaign.mazes.get(level).cloned() else {
+            self.game_over = true;
+            events.push(SimEvent::GameOver);
+            return;
+        };
+
+        campaign.eaten_food = 0;
+        campaign.exit = None;
+
+        self.width = maze.width;
+        self.height = maze.height;
+        self.free_positions = FreePositions::from_positions(
+            maze.width,
+            maze.height,
+            maze.food_positions(false),
+            &mut self.rng,
+        );
+        self.diplopods = vec![Diplopod::new(maze.start)];
+        self.diplopods[0].direction = maze.direction;
+        self.walls = maze.walls.clone();
+        self.food.clear();
+        self.antidote_ticks = 0;
+        self.super_food_countdown = SUPER_FOOD_INTERVAL;
+        self.super_food_ticks = 0;
+        self.init_food(maze.food);
+    }
+
+    /// Awards points to the player. There is no score in the versus mode.
+    fn award(&mut self, points: u16, reason: ScoreReason, events: &mut Vec<SimEvent>) {
+        if self.mode == GameMode::Versus {
+            return;
+        }
+
+        self.score = self.score.saturating_add(points);
+        events.push(SimEvent::Scored(points, reason));
+    }
+
+    fn spawn_food(&mut self, kind: FoodKind, events: &mut Vec<SimEvent>) {
+        if let Some(pos) = self.place_food(kind) {
+            events.push(SimEvent::FoodSpawned(pos, kind));
+        }
+    }
+
+    /// Appends `amount` segments at the position the tail occupied before the last move.
+    fn grow(&mut self, player: usize, last_tail_position: Position, amount: u8) {
+        let segments = &mut self.diplopods[player].segments;
+        for _ in 0..amount {
+            segments.push(last_tail_position);
+        }
+    }
+
+    /// Removes `amount` segments from the tail. The head is never removed.
+    fn shrink(&mut self, player: usize, amount: u8) {
+        let segments = &mut self.diplopods[player].segments;
+        let len = segments.len().saturating_sub(amount as usize).max(1);
+        segments.truncate(len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 42;
+
+    /// A classic game without food, so that nothing but the walls is in the way.
+    fn empty_sim() -> GameSim {
+        let mut sim = GameSim::new(12, 8, GameMode::Classic, SEED);
+        sim.food.clear();
+        sim
+    }
+
+    #[test]
+    fn same_seed_and_inputs_result_in_the_same_game() {
+        let mut first = GameSim::new(20, 15, GameMode::Classic, SEED);
+        let mut second = GameSim::new(20, 15, GameMode::Classic, SEED);
+        assert_eq!(first.food(), second.food());
+
+        let inputs = [IVec2::X, IVec2::ZERO, IVec2::Y, IVec2::ZERO, IVec2::NEG_X];
+        for &direction in inputs.iter().cycle().take(40) {
+            assert_eq!(first.step(direction), second.step(direction));
+            assert_eq!(first.segments(), second.segments());
+            assert_eq!(first.food(), second.food());
+        }
+        assert_eq!(first.score(), second.score());
+    }
+
+    #[test]
+    fn hitting_a_wall_ends_the_game() {
+        let mut sim = empty_sim();
+
+        for _ in 0..4 {
+            assert!(!sim.step(IVec2::X).contains(&SimEvent::GameOver));
+        }
+        assert_eq!(sim.head(), Position { x: 10, y: 4 });
+
+        assert!(sim.step(IVec2::X).contains(&SimEvent::GameOver));
+        assert!(sim.is_game_over());
+    }
+
+    #[test]
+    fn hitting_a_segment_ends_the_game() {
+        let mut sim = empty_sim();
+        sim.diplopods[0].direction = IVec2::Y;
+        sim.diplopods[0].segments = vec![
+            Position { x: 5, y: 4 },
+            Position { x: 5, y: 3 },
+            Position { x: 6, y: 3 },
+            Position { x: 6, y: 4 },
+            Position { x: 6, y: 5 },
+        ];
+
+        assert!(sim.step(IVec2::X).contains(&SimEvent::GameOver));
+        assert!(sim.is_game_over());
+    }
+
+    #[test]
+    fn eating_food_grows_the_diplopod() {
+        let mut sim = empty_sim();
+        let food = Position { x: 7, y: 4 };
+        sim.food.push((food, FoodKind::Normal));
+
+        let events = sim.step(IVec2::X);
+
+        assert!(events.contains(&SimEvent::Ate(food, FoodKind::Normal)));
+        assert!(events.contains(&SimEvent::Grew(1)));
+        assert_eq!(sim.segments(), [food, Position { x: 6, y: 4 }]);
+        assert!(sim.score() > 0);
+    }
+
+    #[test]
+    fn reversing_and_diagonal_directions_are_ignored() {
+        let mut sim = empty_sim();
+        sim.diplopods[0].direction = IVec2::X;
+        sim.diplopods[0].segments = vec![Position { x: 6, y: 4 }, Position { x: 5, y: 4 }];
+
+        sim.step(IVec2::NEG_X);
+        assert_eq!(sim.head(), Position { x: 7, y: 4 });
+        assert_eq!(sim.direction(), IVec2::X);
+
+        sim.step(IVec2::ONE);
+        assert_eq!(sim.head(), Position { x: 8, y: 4 });
+        assert!(!sim.is_game_over());
+    }
+
+    #[test]
+    fn two_quick_turns_are_applied_one_per_tick() {
+        let mut sim = empty_sim();
+        sim.diplopods[0].direction = IVec2::X;
+        sim.diplopods[0].segments = vec![Position { x: 6, y: 4 }, Position { x: 5, y: 4 }];
+
+        sim.turn(0, IVec2::Y);
+        sim.turn(0, IVec2::NEG_X);
+
+        sim.step(IVec2::ZERO);
+        assert_eq!(sim.head(), Position { x: 6, y: 5 });
+        assert_eq!(sim.direction(), IVec2::Y);
+
+        sim.step(IVec2::ZERO);
+        assert_eq!(sim.head(), Position { x: 5, y: 5 });
+        assert_eq!(sim.direction(), IVec2::NEG_X);
+    }
+
+    #[test]
+    fn an_immediate_reversal_is_dropped() {
+        let mut sim = empty_sim();
+        sim.diplopods[0].direction = IVec2::X;
+        sim.diplopods[0].segments = vec![Position { x: 6, y: 4 }, Position { x: 5, y: 4 }];
+
+        sim.turn(0, IVec2::NEG_X);
+        sim.turn(0, IVec2::Y);
+
+        sim.step(IVec2::ZERO);
+        assert_eq!(sim.head(), Position { x: 6, y: 5 });
+
+        sim.step(IVec2::ZERO);
+        assert_eq!(sim.head(), Position { x: 6, y: 6 });
+        assert!(!sim.is_game_over());
+    }
+
+    #[test]
+    fn eating_poison_shrinks_the_diplopod() {
+        let mut sim = empty_sim();
+        sim.diplopods[0].direction = IVec2::X;
+        sim.diplopods[0].segments = vec![Position { x: 6, y: 4 }, Position { x: 5, y: 4 }];
+        let poison = Position { x: 7, y: 4 };
+        sim.food.push((poison, FoodKind::Poison));
+
+        let events = sim.step(IVec2::ZERO);
+        assert!(events.contains(&SimEvent::Ate(poison, FoodKind::Poison)));
+        assert!(events.contains(&SimEvent::Shrank(1)));
+        assert_eq!(sim.segments(), [poison]);
+        assert_eq!(sim.score(), 0);
+
+        // without a segment left, the next poison ends the game
+        sim.food.clear();
+        sim.food.push((Position { x: 8, y: 4 }, FoodKind::Poison));
+        assert!(sim.step(IVec2::ZERO).contains(&SimEvent::GameOver));
+    }
+
+    #[test]
+    fn an_antidote_turns_poison_into_food_for_a_while() {
+        let mut sim = empty_sim();
+        let antidote = Position { x: 7, y: 4 };
+        sim.food.push((antidote, FoodKind::Antidote));
+
+        sim.step(IVec2::X);
+        assert!(sim.is_antidote_active());
+        assert_eq!(sim.segments().len(), 1);
+
+        let poison = Position { x: 8, y: 4 };
+        sim.food.clear();
+        sim.food.push((poison, FoodKind::Poison));
+
+        let events = sim.step(IVec2::ZERO);
+        assert!(events.contains(&SimEvent::Ate(poison, FoodKind::Normal)));
+        assert!(events.contains(&SimEvent::Grew(1)));
+        assert_eq!(sim.segments().len(), 2);
+
+        // the last tick of the antidote still protects, poison hurts again afterwards
+        sim.antidote_ticks = 1;
+        sim.food.clear();
+        sim.step(IVec2::ZERO);
+        assert!(!sim.is_antidote_active());
+
+        let poison = Position { x: 10, y: 4 };
+        sim.food.push((poison, FoodKind::Poison));
+        let events = sim.step(IVec2::ZERO);
+        assert!(events.contains(&SimEvent::Ate(poison, FoodKind::Poison)));
+        assert!(events.contains(&SimEvent::Shrank(1)));
+        assert_eq!(sim.segments().len(), 1);
+    }
+
+    #[test]
+    fn super_food_disappears_after_its_lifetime() {
+        let mut sim = GameSim::new(12, 8, GameMode::Wrap, SEED);
+        sim.food.clear();
+        let super_food = Position { x: 3, y: 1 };
+        sim.food.push((super_food, FoodKind::Super));
+        sim.super_food_ticks = SUPER_FOOD_DURATION;
+
+        for _ in 1..SUPER_FOOD_DURATION {
+            sim.step(IVec2::X);
+        }
+        assert_eq!(sim.food(), [(super_food, FoodKind::Super)]);
+
+        let events = sim.step(IVec2::X);
+        assert!(events.contains(&SimEvent::FoodExpired(super_food, FoodKind::Super)));
+        assert!(sim.food().is_empty());
+        assert_eq!(sim.super_food_ticks(), 0);
+    }
+
+    #[test]
+    fn super_food_grows_the_diplopod_and_scores_a_bonus() {
+        let mut sim = empty_sim();
+        let super_food = Position { x: 7, y: 4 };
+        sim.food.push((super_food, FoodKind::Super));
+        sim.super_food_ticks = SUPER_FOOD_DURATION;
+
+        let events = sim.step(IVec2::X);
+
+        assert!(events.contains(&SimEvent::Ate(super_food, FoodKind::Super)));
+        assert!(events.contains(&SimEvent::Grew(SUPER_FOOD_GROWTH)));
+        assert!(events.contains(&SimEvent::Scored(SUPER_FOOD_POINTS, ScoreReason::Bonus)));
+        assert_eq!(sim.segments().len(), 1 + SUPER_FOOD_GROWTH as usize);
+        assert_eq!(sim.score(), FOOD_POINTS + SUPER_FOOD_POINTS);
+        assert_eq!(sim.super_food_ticks(), 0);
+    }
+
+    #[test]
+    fn quickly_eaten_food_builds_a_combo_that_resets_after_a_pause() {
+        let mut sim = GameSim::new(12, 8, GameMode::Wrap, SEED);
+        sim.food.clear();
+        sim.food.push((Position { x: 7, y: 4 }, FoodKind::Normal));
+
+        let events = sim.step(IVec2::X);
+        assert!(events.contains(&SimEvent::Scored(FOOD_POINTS, ScoreReason::Food)));
+        assert!(
+            !events
+                .iter()
+                .any(|event| matches!(event, SimEvent::Scored(_, ScoreReason::Combo)))
+        );
+
+        sim.food.clear();
+        sim.food.push((Position { x: 8, y: 4 }, FoodKind::Normal));
+        let events = sim.step(IVec2::ZERO);
+        assert!(events.contains(&SimEvent::Scored(1, ScoreReason::Combo)));
+        assert_eq!(sim.score(), 2 * FOOD_POINTS + 1);
+
+        sim.food.clear();
+        for _ in 0..COMBO_TICKS {
+            sim.step(IVec2::ZERO);
+        }
+
+        let next = Position {
+            x: (sim.head().x + 1).rem_euclid(12),
+            y: 4,
+        };
+        sim.food.push((next, FoodKind::Normal));
+        let events = sim.step(IVec2::ZERO);
+        assert!(events.contains(&SimEvent::Ate(next, FoodKind::Normal)));
+        assert!(
+            !events
+                .iter()
+                .any(|event| matches!(event, SimEvent::Scored(_, ScoreReason::Combo)))
+        );
+        assert_eq!(sim.combo, 0);
+    }
+
+    #[test]
+    fn surviving_awards_points_every_interval() {
+        let mut sim = GameSim::new(12, 8, GameMode::Wrap, SEED);
+        sim.food.clear();
+        let survival = SimEvent::Scored(SURVIVAL_POINTS, ScoreReason::Survival);
+
+        for _ in 1..SURVIVAL_TICKS {
+            assert!(!sim.step(IVec2::X).contains(&survival));
+        }
+        assert_eq!(sim.score(), 0);
+
+        assert!(sim.step(IVec2::X).contains(&survival));
+        assert_eq!(sim.score(), SURVIVAL_POINTS);
+
+        for _ in 0..SURVIVAL_TICKS {
+            sim.step(IVec2::X);
+        }
+        assert_eq!(sim.score(), 2 * SURVIVAL_POINTS);
+    }
+
+    #[test]
+    fn the_head_wraps_around_every_edge() {
+        let edges = [
+            (Position { x: 11, y: 4 }, IVec2::X, Position { x: 0, y: 4 }),
+            (
+                Position { x: 0, y: 4 },
+                IVec2::NEG_X,
+                Position { x: 11, y: 4 },
+            ),
+            (Position { x: 6, y: 7 }, IVec2::Y, Position { x: 6, y: 0 }),
+            (
+                Position { x: 6, y: 0 },
+                IVec2::NEG_Y,
+                Position { x: 6, y: 7 },
+            ),
+        ];
+
+        for (head, direction, wrapped) in edges {
+            let mut sim = GameSim::new(12, 8, GameMode::Wrap, SEED);
+            sim.food.clear();
+            sim.diplopods[0].segments = vec![head];
+
+            sim.step(direction);
+
+            assert_eq!(sim.head(), wrapped);
+            assert!(!sim.is_game_over());
+        }
+    }
+
+    #[test]
+    fn crossing_an_edge_ends_the_classic_game() {
+        let edges = [
+            (Position { x: 11, y: 4 }, IVec2::X),
+            (Position { x: 0, y: 4 }, IVec2::NEG_X),
+            (Position { x: 6, y: 7 }, IVec2::Y),
+            (Position { x: 6, y: 0 }, IVec2::NEG_Y),
+        ];
+
+        for (head, direction) in edges {
+            // even without the walls along the border
+            let mut sim = empty_sim();
+            sim.walls.clear();
+            sim.diplopods[0].segments = vec![head];
+
+            assert!(sim.step(direction).contains(&SimEvent::GameOver));
+            assert!(sim.is_game_over());
+        }
+    }
+
+    /// A maze of the campaign with the start in the center, heading right.
+    fn campaign_maze(name: &str) -> Maze {
+        format!(
+            "name {}
+direction right
+food 4
+############
+#..........#
+#..........#
+#....S.....#
+#..........#
+#..........#
+#..........#
+############
+",
+            name
+        )
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn the_exit_opens_after_the_last_food_and_leads_to_the_next_level() {
+        let mazes = vec![campaign_maze("First"), campaign_maze("Second")];
+        let mut sim = GameSim::campaign(mazes, 0, SEED);
+        sim.food.clear();
+        sim.campaign.as_mut().unwrap().eaten_food = EXIT_FOOD - 1;
+        sim.food.push((Position { x: 6, y: 4 }, FoodKind::Normal));
+
+        let events = sim.step(IVec2::ZERO);
+        let exit = sim.exit().unwrap();
+        assert!(events.contains(&SimEvent::ExitOpened(exit)));
+        assert!(!sim.walls().contains(&exit));
+
+        // the exit is moved in front of the head to go through it
+        let score = sim.score();
+        sim.food.clear();
+        sim.campaign.as_mut().unwrap().exit = Some(Position { x: 7, y: 4 });
+
+        let events = sim.step(IVec2::ZERO);
+        assert!(events.contains(&SimEvent::LevelCompleted(1)));
+        assert!(!sim.is_game_over());
+        assert_eq!(sim.campaign_level(), Some(1));
+        assert_eq!(sim.exit(), None);
+        assert_eq!(sim.segments(), [Position { x: 5, y: 4 }]);
+        assert_eq!(sim.score(), score);
+
+        // the last level ends the game
+        sim.campaign.as_mut().unwrap().exit = Some(Position { x: 6, y: 4 });
+        let events = sim.step(IVec2::ZERO);
+        assert!(events.contains(&SimEvent::LevelCompleted(2)));
+        assert!(events.contains(&SimEvent::GameOver));
+    }
+
+    /// A round of the versus mode without food.
+    fn versus_sim() -> GameSim {
+        let mut sim = GameSim::versus(12, 8, SEED);
+        sim.food.clear();
+        sim
+    }
+
+    #[test]
+    fn heads_that_meet_end_the_round_in_a_draw() {
+        let mut sim = versus_sim();
+        sim.diplopods[0].segments = vec![Position { x: 5, y: 4 }];
+        sim.diplopods[1].segments = vec![Position { x: 7, y: 4 }];
+
+        let events = sim.step_players(&[IVec2::X, IVec2::NEG_X]);
+
+        assert!(events.contains(&SimEvent::GameOver));
+        assert!(sim.is_game_over());
+        assert_eq!(sim.winner(), None);
+    }
+
+    #[test]
+    fn hitting_the_other_diplopod_loses_the_round() {
+        let mut sim = versus_sim();
+        sim.diplopods[0].segments = vec![Position { x: 6, y: 4 }];
+        sim.diplopods[1].segments = vec![
+            Position { x: 7, y: 5 },
+            Position { x: 7, y: 4 },
+            Position { x: 7, y: 3 },
+        ];
+
+        assert_eq!(sim.winner(), None);
+        let events = sim.step_players(&[IVec2::X, IVec2::Y]);
+
+        assert!(events.contains(&SimEvent::GameOver));
+        assert_eq!(sim.winner(), Some(1));
+        assert_eq!(sim.score(), 0);
+    }
+
+    #[test]
+    fn the_round_goes_on_while_both_diplopods_move_freely() {
+        let mut sim = versus_sim();
+
+        sim.step_players(&[IVec2::Y, IVec2::NEG_Y]);
+
+        assert!(!sim.is_game_over());
+        assert_eq!(sim.winner(), None);
+        assert_eq!(sim.player_segments(0), [Position { x: 4, y: 5 }]);
+        assert_eq!(sim.player_segments(1), [Position { x: 7, y: 3 }]);
+    }
+}
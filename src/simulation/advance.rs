@@ -0,0 +1,84 @@
+use super::{GameMode, GameSim, ScoreReason, SimEvent};
+use crate::{
+    campaign::{ExitOpened, LevelCompleted},
+    diplopod::{Growth, Shrink},
+    food::{FoodEaten, SpawnFood},
+    gameover::GameOver,
+    replay::{Playback, Recording, ReplayInput},
+    score::Scored,
+};
+use bevy::prelude::*;
+
+/// Advances the simulation by one tick with the next queued turn of each diplopod, or the
+/// direction of the replay that is played back, and forwards what happened to the systems that
+/// render the game. The points that follow eaten food are added to it, so that each food shows
+/// what it was worth. Every direction change is recorded, except in the versus mode, which has no
+/// replays.
+pub fn advance(
+    mut sim: ResMut<GameSim>,
+    playback: Option<Res<Playback>>,
+    mut recording: ResMut<Recording>,
+    mut food_eaten_writer: EventWriter<FoodEaten>,
+    mut growth_writer: EventWriter<Growth>,
+    mut shrink_writer: EventWriter<Shrink>,
+    mut spawn_food_writer: EventWriter<SpawnFood>,
+    mut scored_writer: EventWriter<Scored>,
+    mut game_over_writer: EventWriter<GameOver>,
+    mut exit_opened_writer: EventWriter<ExitOpened>,
+    mut level_completed_writer: EventWriter<LevelCompleted>,
+) {
+    let mut directions = vec![IVec2::ZERO; sim.players()];
+    if let Some(playback) = playback {
+        directions[0] = playback.replay.direction_at(sim.tick());
+    }
+
+    let tick = sim.tick();
+    let previous_direction = sim.direction();
+    let events = sim.step_players(&directions);
+
+    if sim.mode() != GameMode::Versus && sim.direction() != previous_direction {
+        recording.0.push(ReplayInput {
+            tick,
+            direction: sim.direction(),
+        });
+    }
+
+    let mut food_eaten = Vec::new();
+
+    for event in events {
+        match event {
+            SimEvent::Ate(pos, kind) => {
+                food_eaten.push(FoodEaten(pos, kind, 0));
+            }
+            SimEvent::Grew(amount) => {
+                growth_writer.write(Growth(amount));
+            }
+            SimEvent::Shrank(amount) => {
+                shrink_writer.write(Shrink(amount));
+            }
+            SimEvent::FoodSpawned(pos, kind) => {
+                spawn_food_writer.write(SpawnFood(pos, kind));
+            }
+            SimEvent::FoodExpired(_, _) => (),
+            SimEvent::Scored(points, reason) => {
+                if reason != ScoreReason::Survival
+                    && let Some(FoodEaten(_, _, food_points)) = food_eaten.last_mut()
+                {
+                    *food_points += points;
+                }
+                scored_writer.write(Scored(points, reason));
+            }
+            SimEvent::ExitOpened(pos) => {
+                exit_opened_writer.write(ExitOpened(pos));
+            }
+            SimEvent::LevelCompleted(level) => {
+                level_completed_writer.write(LevelCompleted(level));
+            }
+            SimEvent::GameOver => {
+                game_over_writer.write(GameOver);
+            }
+        }
+    }
+
+    food_eaten_writer.write_batch(food_eaten);
+}
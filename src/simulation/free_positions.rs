@@ -0,0 +1,68 @@
+use rand::{Rng, seq::SliceRandom};
+
+use crate::components::Position;
+
+#[derive(Clone, Default)]
+pub struct FreePositions {
+    pub positions: Vec<Position>,
+    width: i32,
+    height: i32,
+}
+
+impl FreePositions {
+    pub fn new(width: i32, height: i32, rng: &mut impl Rng) -> Self {
+        let positions = Self::new_positions(width, height, rng);
+        Self {
+            positions,
+            width,
+            height,
+        }
+    }
+
+    /// Creates free positions from the given positions of a `width` times `height` arena.
+    pub fn from_positions(
+        width: i32,
+        height: i32,
+        mut positions: Vec<Position>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        positions.shuffle(rng);
+        Self {
+            positions,
+            width,
+            height,
+        }
+    }
+
+    fn new_positions(width: i32, height: i32, rng: &mut impl Rng) -> Vec<Position> {
+        let mut positions = Vec::new();
+
+        for x in 0..width {
+            for y in 0..height {
+                positions.push(Position { x, y });
+            }
+        }
+
+        positions.shuffle(rng);
+
+        positions
+    }
+
+    pub fn shuffle(&mut self, rng: &mut impl Rng) {
+        self.positions.shuffle(rng);
+    }
+
+    pub fn remove(&mut self, position: &Position) {
+        self.positions.retain(|&p| p != *position);
+    }
+
+    pub fn remove_all(&mut self, positions: &Vec<Position>) {
+        for position in positions {
+            self.remove(position);
+        }
+    }
+
+    pub fn reset(&mut self, rng: &mut impl Rng) {
+        self.positions = Self::new_positions(self.width, self.height, rng);
+    }
+}
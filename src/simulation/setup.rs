@@ -0,0 +1,107 @@
+use super::{GameMode, GameSim};
+use crate::{
+    campaign::{self, CampaignProgress},
+    editor::TestPlay,
+    maze::{Maze, Mazes, PlayedMaze, SelectedMaze},
+    replay::Playback,
+    resources::{ArenaSize, Seed, ShownArena},
+};
+use bevy::prelude::*;
+
+/// The options a game is started with.
+struct GameOptions<'a> {
+    arena: ArenaSize,
+    mode: GameMode,
+    maze: Option<&'a Handle<Maze>>,
+    /// Index of the level a campaign starts with.
+    campaign: usize,
+}
+
+/// Starts the game with the options of the replay that is played back or else with the options
+/// chosen in the menu. The options are only read: a maze or a campaign level only changes the
+/// arena that is shown.
+pub fn init(
+    mut commands: Commands,
+    mut seed: ResMut<Seed>,
+    arena_size: Res<ArenaSize>,
+    mode: Res<GameMode>,
+    selected_maze: Res<SelectedMaze>,
+    mazes: Res<Assets<Maze>>,
+    built_in_mazes: Res<Mazes>,
+    progress: Res<CampaignProgress>,
+    playback: Option<Res<Playback>>,
+    test_play: Option<Res<TestPlay>>,
+) {
+    let seed = match &playback {
+        Some(playback) => playback.replay.seed,
+        None => seed.next_game(),
+    };
+
+    let options = match (&playback, &test_play) {
+        (Some(playback), _) => GameOptions {
+            arena: playback.replay.arena,
+            mode: playback.replay.mode,
+            maze: playback.maze.as_ref(),
+            campaign: playback.replay.campaign,
+        },
+        // the maze of the editor isn't played in the modes with their own arenas
+        (None, Some(test_play)) => GameOptions {
+            arena: *arena_size,
+            mode: match *mode {
+                GameMode::Campaign | GameMode::Versus => GameMode::Classic,
+                mode => mode,
+            },
+            maze: Some(&test_play.0),
+            campaign: 0,
+        },
+        (None, None) => GameOptions {
+            arena: *arena_size,
+            mode: *mode,
+            maze: selected_maze.0.as_ref(),
+            campaign: progress.unlocked,
+        },
+    };
+
+    let (sim, maze) = new_sim(seed, &options, &mazes, &built_in_mazes);
+    commands.insert_resource(ShownArena(ArenaSize::new(sim.width(), sim.height())));
+    commands.insert_resource(PlayedMaze(maze));
+    commands.insert_resource(sim);
+}
+
+/// Creates the simulation and returns the maze it is played in, if any.
+fn new_sim(
+    seed: u64,
+    options: &GameOptions,
+    mazes: &Assets<Maze>,
+    built_in_mazes: &Mazes,
+) -> (GameSim, Option<Handle<Maze>>) {
+    let (arena, mode) = (options.arena, options.mode);
+
+    // the rounds are played in the arena without a maze
+    if mode == GameMode::Versus {
+        return (GameSim::versus(arena.width, arena.height, seed), None);
+    }
+
+    if mode == GameMode::Campaign {
+        match campaign::campaign_mazes(built_in_mazes, mazes) {
+            Some(mazes) if !mazes.is_empty() => {
+                let level = options.campaign.min(mazes.len() - 1);
+                return (GameSim::campaign(mazes, level, seed), None);
+            }
+            _ => warn!("The mazes of the campaign aren't loaded, playing without them"),
+        }
+    }
+
+    let maze = options.maze.and_then(|handle| {
+        let maze = mazes.get(handle);
+        if maze.is_none() {
+            warn!("Maze {:?} isn't loaded, playing without it", handle.path());
+        }
+        maze.map(|maze| (maze, handle))
+    });
+
+    match maze {
+        Some((maze, handle)) => (GameSim::with_maze(maze, mode, seed), Some(handle.clone())),
+        None => (GameSim::new(arena.width, arena.height, mode, seed), None),
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    food::{FoodEaten, SpawnFood},
+    gameover::GameOver,
+    resources::Paused,
+    simulation::FoodKind,
+};
+use bevy::{audio::Volume, prelude::*};
+
+/// Plays the sound effects of the game with the volume of the settings.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup)
+            .add_systems(
+                Update,
+                (
+                    (
+                        eat.run_if(on_event::<FoodEaten>),
+                        spawn_food.run_if(on_event::<SpawnFood>),
+                        game_over.run_if(on_event::<GameOver>),
+                    )
+                        .run_if(resource_exists::<Sounds>),
+                    pause.run_if(resource_added::<Paused>),
+                    unpause.run_if(resource_removed::<Paused>),
+                ),
+            )
+            .init_resource::<SoundSettings>();
+    }
+}
+
+/// Volume of the sound effects and whether they are muted, stored in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Resource)]
+pub struct SoundSettings {
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Sounds {
+    eat_food: Handle<AudioSource>,
+    eat_poison: Handle<AudioSource>,
+    antidote: Handle<AudioSource>,
+    super_food: Handle<AudioSource>,
+    special_spawn: Handle<AudioSource>,
+    game_over: Handle<AudioSource>,
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Sounds {
+        eat_food: asset_server.load("audio/eat_food.ogg"),
+        eat_poison: asset_server.load("audio/eat_poison.ogg"),
+        antidote: asset_server.load("audio/antidote.ogg"),
+        super_food: asset_server.load("audio/super_food.ogg"),
+        special_spawn: asset_server.load("audio/special_spawn.ogg"),
+        game_over: asset_server.load("audio/game_over.ogg"),
+    });
+}
+
+/// Spawns an entity that plays the sound once with the configured volume and despawns afterwards.
+fn play(commands: &mut Commands, sound: &Handle<AudioSource>, settings: &SoundSettings) {
+    if settings.muted {
+        return;
+    }
+
+    commands.spawn((
+        AudioPlayer::new(sound.clone()),
+        PlaybackSettings::DESPAWN.with_volume(Volume::Linear(settings.volume)),
+    ));
+}
+
+fn eat(
+    mut commands: Commands,
+    mut reader: EventReader<FoodEaten>,
+    sounds: Res<Sounds>,
+    settings: Res<SoundSettings>,
+) {
+    for FoodEaten(_, kind, _) in reader.read() {
+        let sound = match kind {
+            FoodKind::Normal => &sounds.eat_food,
+            FoodKind::Poison => &sounds.eat_poison,
+            FoodKind::Antidote => &sounds.antidote,
+            FoodKind::Super => &sounds.super_food,
+        };
+
+        play(&mut commands, sound, &settings);
+    }
+}
+
+/// Announces the super food.
+fn spawn_food(
+    mut commands: Commands,
+    mut reader: EventReader<SpawnFood>,
+    sounds: Res<Sounds>,
+    settings: Res<SoundSettings>,
+) {
+    for SpawnFood(_, kind) in reader.read() {
+        if *kind == FoodKind::Super {
+            play(&mut commands, &sounds.special_spawn, &settings);
+        }
+    }
+}
+
+fn game_over(
+    mut commands: Commands,
+    mut reader: EventReader<GameOver>,
+    sounds: Res<Sounds>,
+    settings: Res<SoundSettings>,
+) {
+    if reader.read().next().is_some() {
+        play(&mut commands, &sounds.game_over, &settings);
+    }
+}
+
+/// Pauses all sounds while the game is paused.
+fn pause(sinks: Query<&AudioSink>) {
+    for sink in sinks.iter() {
+        sink.pause();
+    }
+}
+
+/// Continues all sounds when the game is continued.
+fn unpause(sinks: Query<&AudioSink>) {
+    for sink in sinks.iter() {
+        sink.play();
+    }
+}
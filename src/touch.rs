@@ -0,0 +1,174 @@
+use crate::{
+    components::{GameState, OnGameScreen, Phase},
+    replay::Playback,
+    resources::Paused,
+    simulation::GameSim,
+};
+use bevy::prelude::*;
+
+/// Adds an optional on-screen D-pad for touch screens. Without it the diplopod is steered by
+/// swiping and the game is paused by tapping.
+pub struct TouchPlugin;
+
+/// The D-pad is shown by default on phones and tablets.
+const SHOW_DPAD: bool = cfg!(any(target_os = "android", target_os = "ios"));
+const BUTTON_SIZE: f32 = 72.0;
+const BUTTON_TEXT_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.6);
+const BUTTON_BACKGROUND_COLOR: Color = Color::srgba(0.25, 0.25, 0.25, 0.4);
+const BUTTON_PRESSED_BACKGROUND_COLOR: Color = Color::srgba(0.5, 0.5, 0.5, 0.6);
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Game),
+            spawn_dpad.run_if(|touch_controls: Res<TouchControls>| touch_controls.dpad),
+        )
+        .add_systems(
+            Update,
+            (
+                dpad.run_if(not(resource_exists::<Paused>))
+                    .run_if(not(resource_exists::<Playback>)),
+                dpad_pause,
+                update_dpad_buttons,
+            )
+                .in_set(Phase::Input)
+                .run_if(in_state(GameState::Game)),
+        )
+        .init_resource::<TouchControls>();
+    }
+}
+
+/// Whether the on-screen D-pad is shown.
+#[derive(Resource)]
+pub struct TouchControls {
+    pub dpad: bool,
+}
+
+impl Default for TouchControls {
+    fn default() -> Self {
+        Self { dpad: SHOW_DPAD }
+    }
+}
+
+/// A button of the D-pad that steers the diplopod in the direction.
+#[derive(Component)]
+struct DPadButton(IVec2);
+
+/// The button in the center of the D-pad that pauses and continues the game.
+#[derive(Component)]
+struct PauseButton;
+
+/// Turns the diplopod of the first player when a button of the D-pad is pressed.
+fn dpad(
+    buttons: Query<(&Interaction, &DPadButton), Changed<Interaction>>,
+    mut sim: ResMut<GameSim>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            sim.turn(0, button.0);
+        }
+    }
+}
+
+fn dpad_pause(
+    mut commands: Commands,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<PauseButton>)>,
+    paused: Option<Res<Paused>>,
+) {
+    if !buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+
+    if paused.is_some() {
+        commands.remove_resource::<Paused>();
+    } else {
+        commands.init_resource::<Paused>();
+    }
+}
+
+fn update_dpad_buttons(
+    mut buttons: Query<
+        (&Interaction, &mut BackgroundColor),
+        (
+            Changed<Interaction>,
+            Or<(With<DPadButton>, With<PauseButton>)>,
+        ),
+    >,
+) {
+    for (interaction, mut background_color) in buttons.iter_mut() {
+        background_color.0 = if *interaction == Interaction::Pressed {
+            BUTTON_PRESSED_BACKGROUND_COLOR
+        } else {
+            BUTTON_BACKGROUND_COLOR
+        };
+    }
+}
+
+/// Creates the D-pad in the bottom right corner of the window, a grid of three times three
+/// buttons with the directions on the edges and the pause button in the center.
+fn spawn_dpad(mut commands: Commands) {
+    let button_node = Node {
+        width: Val::Px(BUTTON_SIZE),
+        height: Val::Px(BUTTON_SIZE),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    let rows = [
+        [None, Some(("^", IVec2::Y)), None],
+        [
+            Some(("<", IVec2::NEG_X)),
+            Some(("||", IVec2::ZERO)),
+            Some((">", IVec2::X)),
+        ],
+        [None, Some(("v", IVec2::NEG_Y)), None],
+    ];
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(20.0),
+                bottom: Val::Px(20.0),
+                display: Display::Grid,
+                grid_template_columns: RepeatedGridTrack::px(3, BUTTON_SIZE),
+                grid_template_rows: RepeatedGridTrack::px(3, BUTTON_SIZE),
+                ..default()
+            },
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            for button in rows.iter().flatten() {
+                let Some((label, direction)) = button else {
+                    parent.spawn(Node::default());
+                    continue;
+                };
+
+                let mut entity = parent.spawn((
+                    Button,
+                    button_node.clone(),
+                    BackgroundColor(BUTTON_BACKGROUND_COLOR),
+                ));
+                if *direction == IVec2::ZERO {
+                    entity.insert(PauseButton);
+                } else {
+                    entity.insert(DPadButton(*direction));
+                }
+
+                entity.with_children(|parent| {
+                    parent.spawn((
+                        Text::new(*label),
+                        TextFont {
+                            font_size: 40.0,
+                            ..default()
+                        },
+                        TextColor(BUTTON_TEXT_COLOR),
+                    ));
+                });
+            }
+        });
+}
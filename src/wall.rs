@@ -1,9 +1,9 @@
 use crate::{
-    ARENA_HEIGHT, ARENA_WIDTH,
-    components::{GameState, Phase, Position},
+    campaign::LevelCompleted,
+    components::{GameState, GridLayer, Phase, Position},
     resources::TileSize,
 };
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
 
 mod setup;
 
@@ -16,44 +16,50 @@ pub struct WallPlugin;
 
 impl Plugin for WallPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(OnEnter(GameState::Game), setup::init)
-            .add_systems(
-                Update,
-                (position_translation,)
-                    .after(Phase::Movement)
-                    .run_if(in_state(GameState::Game)),
-            );
+        app.add_systems(
+            OnEnter(GameState::Game),
+            setup::init.after(crate::simulation::setup::init),
+        )
+        .add_systems(
+            Update,
+            (
+                (crate::despawn_screen::<Wall>, setup::init)
+                    .chain()
+                    .run_if(in_state(GameState::Game))
+                    .run_if(on_event::<LevelCompleted>),
+                resize.run_if(resource_changed::<TileSize>),
+            )
+                .chain()
+                .after(Phase::Movement),
+        );
     }
 }
 
-fn position_translation(
-    windows: Query<&Window, With<PrimaryWindow>>,
-    mut q: Query<(&Position, &mut Transform), With<Wall>>,
+/// Components of a wall at the position. The walls of the game and of the editor are placed and
+/// resized alike.
+pub fn wall_bundle(
+    pos: Position,
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+) -> impl Bundle {
+    (
+        Wall,
+        pos,
+        GridLayer::Wall,
+        Mesh2d(mesh),
+        MeshMaterial2d(material),
+    )
+}
+
+/// Rebuilds the mesh of the walls for a changed tile size.
+fn resize(
     tile_size: Res<TileSize>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut q: Query<&mut Mesh2d, With<Wall>>,
 ) {
-    fn convert(pos: f32, bound_window: f32, bound_game: f32, tile_size: f32) -> f32 {
-        pos / bound_game * bound_window - (bound_window / 2.) + (tile_size / 2.)
-    }
+    let mesh = meshes.add(Rectangle::new(tile_size.0 as f32, tile_size.0 as f32));
 
-    let Ok(window) = windows.single() else {
-        return;
-    };
-
-    for (pos, mut transform) in q.iter_mut() {
-        transform.translation = Vec3::new(
-            convert(
-                pos.x as f32,
-                window.width(),
-                ARENA_WIDTH as f32,
-                tile_size.0 as f32,
-            ),
-            convert(
-                pos.y as f32,
-                window.height(),
-                ARENA_HEIGHT as f32,
-                tile_size.0 as f32,
-            ),
-            2.0,
-        );
+    for mut mesh2d in q.iter_mut() {
+        mesh2d.0 = mesh.clone();
     }
 }
@@ -0,0 +1,228 @@
+use crate::{
+    GameState,
+    controls::{Action, Controls},
+    diplopod::{PLAYER_NAMES, diplopod_color},
+    highscore::InitialDelay,
+    simulation::GameSim,
+};
+use bevy::{color::palettes::css::ANTIQUE_WHITE, prelude::*};
+
+/// Adds the screen that shows the winner of a round of the versus mode and the rounds won so far.
+pub struct WinnerPlugin;
+
+const TITLE_COLOR: Color = Color::Srgba(ANTIQUE_WHITE);
+const HEADLINE_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const BUTTON_TEXT_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
+const BUTTON_BACKGROUND_COLOR: Color = Color::srgb(0.15, 0.15, 0.15);
+
+#[derive(Component)]
+struct OnWinnerScreen;
+
+/// Buttons that start the next round or return to the menu, for mice and touch screens.
+#[derive(Clone, Copy, Component)]
+enum WinnerButton {
+    Next,
+    Menu,
+}
+
+/// Number of rounds each player won since the versus mode was started from the menu.
+#[derive(Default, Resource)]
+struct Wins([u32; PLAYER_NAMES.len()]);
+
+impl Plugin for WinnerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Winner), setup_winner)
+            .add_systems(
+                Update,
+                (gamepad, keyboard, pointer)
+                    .run_if(in_state(GameState::Winner))
+                    .run_if(not(resource_exists::<InitialDelay>)),
+            )
+            .add_systems(
+                OnExit(GameState::Winner),
+                crate::despawn_screen::<OnWinnerScreen>,
+            )
+            .add_systems(OnEnter(GameState::Menu), reset_wins)
+            .init_resource::<Wins>();
+    }
+}
+
+/// Starts the next round with confirm and returns to the menu with back.
+fn keyboard(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    controls: Res<Controls>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if controls.just_released(Action::Confirm, &keyboard_input) {
+        game_state.set(GameState::Game);
+    }
+
+    if controls.just_released(Action::Back, &keyboard_input) {
+        game_state.set(GameState::Menu);
+    }
+}
+
+/// Works like the keyboard with the buttons of the gamepads.
+fn gamepad(
+    gamepads: Query<&Gamepad>,
+    controls: Res<Controls>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for gamepad in gamepads.iter() {
+        if controls.gamepad_just_released(Action::Confirm, gamepad) {
+            game_state.set(GameState::Game);
+        }
+
+        if controls.gamepad_just_released(Action::Back, gamepad) {
+            game_state.set(GameState::Menu);
+        }
+    }
+}
+
+/// Starts the next round or returns to the menu when the button was clicked or touched.
+fn pointer(
+    buttons: Query<(&Interaction, &WinnerButton), Changed<Interaction>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction == Interaction::Pressed {
+            game_state.set(match button {
+                WinnerButton::Next => GameState::Game,
+                WinnerButton::Menu => GameState::Menu,
+            });
+        }
+    }
+}
+
+fn reset_wins(mut wins: ResMut<Wins>) {
+    *wins = Wins::default();
+}
+
+/// Counts the won round and creates the UI of the winner screen.
+fn setup_winner(mut commands: Commands, sim: Res<GameSim>, mut wins: ResMut<Wins>) {
+    let winner = sim.winner();
+    if let Some(player) = winner {
+        wins.0[player] += 1;
+    }
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            OnWinnerScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .with_children(|parent| {
+                    let (title, color) = match winner {
+                        Some(player) => (
+                            format!("{} wins!", PLAYER_NAMES[player]),
+                            diplopod_color(player),
+                        ),
+                        None => ("Draw!".to_string(), TITLE_COLOR),
+                    };
+
+                    parent.spawn((
+                        Text::new(title),
+                        TextFont {
+                            font_size: 96.0,
+                            ..default()
+                        },
+                        TextColor(color),
+                        Node {
+                            margin: UiRect::all(Val::Px(30.0)),
+                            ..default()
+                        },
+                    ));
+
+                    parent
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (player, name) in PLAYER_NAMES.iter().enumerate() {
+                                parent.spawn((
+                                    Text::new(format!(
+                                        "{} {} ({} long)",
+                                        name,
+                                        wins.0[player],
+                                        sim.player_segments(player).len()
+                                    )),
+                                    TextFont {
+                                        font_size: 48.0,
+                                        ..default()
+                                    },
+                                    TextColor(diplopod_color(player)),
+                                    Node {
+                                        margin: UiRect::all(Val::Px(20.0)),
+                                        ..default()
+                                    },
+                                ));
+                            }
+                        });
+
+                    parent.spawn((
+                        Text::new("Press confirm for the next round or back for the menu"),
+                        TextFont {
+                            font_size: 32.0,
+                            ..default()
+                        },
+                        TextColor(HEADLINE_COLOR),
+                        Node {
+                            margin: UiRect::all(Val::Px(30.0)),
+                            ..default()
+                        },
+                    ));
+
+                    parent
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (button, text) in [
+                                (WinnerButton::Next, "Next round"),
+                                (WinnerButton::Menu, "Menu"),
+                            ] {
+                                parent
+                                    .spawn((
+                                        Button,
+                                        Node {
+                                            width: Val::Px(240.0),
+                                            height: Val::Px(50.0),
+                                            margin: UiRect::all(Val::Px(10.0)),
+                                            justify_content: JustifyContent::Center,
+                                            align_items: AlignItems::Center,
+                                            ..default()
+                                        },
+                                        BackgroundColor(BUTTON_BACKGROUND_COLOR),
+                                        button,
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            Text::new(text),
+                                            TextFont {
+                                                font_size: 32.0,
+                                                ..default()
+                                            },
+                                            TextColor(BUTTON_TEXT_COLOR),
+                                        ));
+                                    });
+                            }
+                        });
+                });
+        });
+
+    commands.init_resource::<InitialDelay>();
+}